@@ -0,0 +1,178 @@
+//! Types that bitfields can be made of ([BitStorage]) and ranges of bits in them ([BitRange]).
+//!
+//! The functions here implement the masking logic shared by fields and bit ranges.
+
+use core::{
+    ops::{ Shl, Shr, BitAnd, BitOrAssign, BitXorAssign, Not },
+    fmt::{ Debug, Display },
+    marker::PhantomData
+};
+
+/// Primitive integer types that can hold bits: the underlying types of bitfields.
+///
+/// Sums up the operations that fields need, so generic code can use `T: BitStorage` as a single bound.
+pub trait BitStorage: Copy + Debug + Display + 'static +
+    Shl<u8, Output=Self> +
+    Shr<u8, Output=Self> +
+    BitAnd<Output=Self> +
+    Not<Output=Self> +
+    BitOrAssign + BitXorAssign + PartialEq
+{
+    /// The number of bits in the type.
+    const BITS: u8;
+
+    /// The value with all bits set.
+    const ONES: Self;
+
+    /// Zero.
+    const ZERO: Self;
+
+    /// One.
+    const ONE: Self;
+
+    /// Addition that wraps around at the type's bounds, like `u8::wrapping_add`.
+    fn wrapping_add(self, rhs: Self) -> Self;
+
+    /// Subtraction that wraps around at the type's bounds, like `u8::wrapping_sub`.
+    fn wrapping_sub(self, rhs: Self) -> Self;
+
+    /// Addition that returns `None` on overflow, like `u8::checked_add`.
+    fn checked_add(self, rhs: Self) -> Option<Self>;
+
+    /// Subtraction that returns `None` on overflow, like `u8::checked_sub`.
+    fn checked_sub(self, rhs: Self) -> Option<Self>;
+
+    /// The number of ones, like `u8::count_ones`.
+    fn count_ones(self) -> u32;
+
+    /// The number of leading zeros, like `u8::leading_zeros`.
+    fn leading_zeros(self) -> u32;
+
+    /// The number of trailing zeros, like `u8::trailing_zeros`.
+    fn trailing_zeros(self) -> u32;
+
+    /// Reverses the order of the bits, like `u8::reverse_bits`.
+    fn reverse_bits(self) -> Self;
+}
+
+macro_rules! impl_bit_storage {
+    ($($ty:ty),*) => {$(
+        impl BitStorage for $ty {
+            const BITS: u8 = <$ty>::BITS as u8;
+            const ONES: Self = !0;
+            const ZERO: Self = 0;
+            const ONE: Self = 1;
+
+            fn wrapping_add(self, rhs: Self) -> Self { <$ty>::wrapping_add(self, rhs) }
+
+            fn wrapping_sub(self, rhs: Self) -> Self { <$ty>::wrapping_sub(self, rhs) }
+
+            fn checked_add(self, rhs: Self) -> Option<Self> { <$ty>::checked_add(self, rhs) }
+
+            fn checked_sub(self, rhs: Self) -> Option<Self> { <$ty>::checked_sub(self, rhs) }
+
+            fn count_ones(self) -> u32 { <$ty>::count_ones(self) }
+
+            fn leading_zeros(self) -> u32 { <$ty>::leading_zeros(self) }
+
+            fn trailing_zeros(self) -> u32 { <$ty>::trailing_zeros(self) }
+
+            fn reverse_bits(self) -> Self { <$ty>::reverse_bits(self) }
+        }
+    )*};
+}
+
+impl_bit_storage!(u8, u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+/// The mask that extracts the lowest `size` bits.
+pub(crate) fn mask<T: BitStorage>(size: u8) -> T {
+    if size >= T::BITS { T::ONES } else { !(T::ONES << size) }
+}
+
+/// Extracts the bits `mask << offset` from `value`.
+pub(crate) fn get<T: BitStorage>(value: T, offset: u8, mask: T) -> T {
+    (value >> offset) & mask
+}
+
+/// Replaces the bits `mask << offset` of `value` with the lowest bits of `new_value`.
+pub(crate) fn set<T: BitStorage>(value: &mut T, offset: u8, mask: T, new_value: T) {
+    let old_value: T = get(*value, offset, mask) << offset;
+
+    *value ^= old_value;
+    *value |= (new_value & mask) << offset
+}
+
+/// The bits `OFFSET..OFFSET + SIZE` of any [BitStorage] type, for when declaring a whole [bitfield](crate::bitfield) isn't worth it.
+///
+/// The range is checked at compile-time: using it with a type that's narrower than `OFFSET + SIZE` bits
+/// (or with `SIZE == 0`) is an error, just like a field that doesn't fit into its bitfield.
+///
+/// Example:
+/// ```
+/// use simple_bitfield::BitRange;
+///
+/// type Middle = BitRange<12, 8>;
+///
+/// let mut value: u32 = 0x1234_5678;
+/// assert_eq!(Middle::get(value), 0x45);
+///
+/// Middle::set(&mut value, 0xAB);
+/// assert_eq!(value, 0x123A_B678);
+///
+/// assert_eq!(Middle::set_checked(&mut value, 0x1FF), Err(0xFF));
+/// assert_eq!(Middle::mask::<u32>(), 0xFF);
+/// ```
+///
+/// ```compile_fail
+/// use simple_bitfield::BitRange;
+///
+/// // Error: bits 12..20 don't fit into `u16`
+/// let bits = BitRange::<12, 8>::get(0x1234u16);
+/// ```
+pub struct BitRange<const OFFSET: u8, const SIZE: u8>;
+
+impl<const OFFSET: u8, const SIZE: u8> BitRange<OFFSET, SIZE> {
+    /// `true` if the range isn't empty and fits into `T`, like [Field::VALID](crate::Field::VALID).
+    pub const fn fits<T: BitStorage>() -> bool {
+        SIZE > 0 && OFFSET as u32 + SIZE as u32 <= T::BITS as u32
+    }
+
+    /// The mask that extracts the lowest `SIZE` bits, like [Field::MASK](crate::Field::MASK).
+    pub fn mask<T: BitStorage>() -> T {
+        let () = Valid::<T, OFFSET, SIZE>::CHECK;
+
+        mask(SIZE)
+    }
+
+    /// Returns the range's bits of `value`.
+    pub fn get<T: BitStorage>(value: T) -> T {
+        get(value, OFFSET, Self::mask())
+    }
+
+    /// Sets the range's bits of `value`. If the new value is wider than the range,
+    /// the value's lowest `SIZE` bits will be used.
+    pub fn set<T: BitStorage>(value: &mut T, new_value: T) {
+        set(value, OFFSET, Self::mask(), new_value)
+    }
+
+    /// Sets the range's bits of `value`. If the new value is wider than the range,
+    /// returns an `Err` result containing the value's lowest `SIZE` bits and doesn't modify `value`.
+    pub fn set_checked<T: BitStorage>(value: &mut T, new_value: T) -> Result<(), T> {
+        let masked = new_value & Self::mask();
+
+        if masked != new_value {
+            Err(masked)
+        } else {
+            Self::set(value, masked);
+
+            Ok(())
+        }
+    }
+}
+
+/// Fails to compile when used with a range that doesn't fit into `T`.
+struct Valid<T, const OFFSET: u8, const SIZE: u8>(PhantomData<T>);
+
+impl<T: BitStorage, const OFFSET: u8, const SIZE: u8> Valid<T, OFFSET, SIZE> {
+    const CHECK: () = assert!(BitRange::<OFFSET, SIZE>::fits::<T>(), "bit range is empty or doesn't fit into the type");
+}
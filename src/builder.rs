@@ -0,0 +1,13 @@
+//! The states of the fields of a bitfield's builder, generated by `#[builder]` in [bitfield](crate::bitfield).
+//!
+//! A builder has one type parameter per named field, which is either [Unset] or [Set].
+//! Each field can be set once, and `build()` compiles only if all fields without a default value are [Set].
+
+/// The state of a builder's field that hasn't been set yet.
+#[derive(Copy, Clone, Debug)]
+pub struct Unset;
+
+/// The state of a builder's field that has been set.
+#[derive(Copy, Clone, Debug)]
+pub struct Set;
+
@@ -0,0 +1,179 @@
+//! Access to fields chosen at runtime: by name (see [Bitfield::get_by_name] and [Bitfield::set_by_name]),
+//! through [FieldDescriptor]s and through `dyn` [DynField]s.
+
+use core::{
+    fmt::{ self, Debug, Display },
+    marker::PhantomData
+};
+use crate::{ bits, Bitfield, Field };
+
+/// Error returned when a field chosen at runtime can't be set.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum FieldError {
+    /// The bitfield has no (named) field with this name.
+    UnknownField,
+    /// The value doesn't fit into the field, which is `size` bits wide.
+    ValueTooWide { size: u8 }
+}
+
+impl Display for FieldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            FieldError::UnknownField => write!(f, "no field with this name"),
+            FieldError::ValueTooWide { size } => write!(f, "value doesn't fit into the field's {} bits", size)
+        }
+    }
+}
+
+/// Describes a field of the bitfield `B` at runtime, so that "which field" can be a value.
+/// Every field's descriptor is [Field::DESCRIPTOR](crate::Field::DESCRIPTOR).
+///
+/// Example:
+/// ```
+/// use simple_bitfield::{ bitfield, Field, DynField, FieldDescriptor };
+///
+/// bitfield! {
+///     struct TestBitfield<u8> {
+///         low: 3,
+///         _: 2,
+///         high: 3
+///     }
+/// }
+///
+/// fn main() {
+///     let fields: [FieldDescriptor<TestBitfield::TestBitfield>; 2] = [
+///         TestBitfield::low::DESCRIPTOR,
+///         TestBitfield::high::DESCRIPTOR
+///     ];
+///
+///     let mut my_bitfield = TestBitfield::new(0);
+///     for (i, field) in fields.iter().enumerate() {
+///         field.set(&mut my_bitfield, i as u8 + 1);
+///     }
+///
+///     assert_eq!(u8::from(my_bitfield), 0b010_00_001);
+///     assert_eq!(fields[1].get(&my_bitfield), 0b010);
+/// }
+/// ```
+pub struct FieldDescriptor<B: Bitfield> {
+    offset: u8,
+    size: u8,
+    mask: B::BaseType,
+    bitfield: PhantomData<fn() -> B>
+}
+
+impl<B: Bitfield> FieldDescriptor<B> {
+    pub(crate) const fn new(offset: u8, size: u8, mask: B::BaseType) -> Self {
+        FieldDescriptor { offset, size, mask, bitfield: PhantomData }
+    }
+}
+
+impl<B: Bitfield, F: Field<B>> From<&F> for FieldDescriptor<B> {
+    fn from(field: &F) -> Self {
+        field.descriptor()
+    }
+}
+
+impl<B: Bitfield> Clone for FieldDescriptor<B> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<B: Bitfield> Copy for FieldDescriptor<B> {}
+
+impl<B: Bitfield> Debug for FieldDescriptor<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("FieldDescriptor")
+            .field("offset", &self.offset)
+            .field("size", &self.size)
+            .field("mask", &self.mask)
+            .finish()
+    }
+}
+
+impl<B: Bitfield> PartialEq for FieldDescriptor<B> {
+    fn eq(&self, other: &Self) -> bool {
+        (self.offset, self.size) == (other.offset, other.size)
+    }
+}
+
+impl<B: Bitfield> Eq for FieldDescriptor<B> {}
+
+/// Object-safe access to a field of the bitfield `B`, so fields can be stored
+/// as `&dyn DynField<B>` and chosen at runtime.
+///
+/// Implemented by [FieldDescriptor], which every field converts into.
+///
+/// Example:
+/// ```
+/// use simple_bitfield::{ bitfield, Field, DynField, FieldDescriptor };
+///
+/// bitfield! {
+///     struct TestBitfield<u8> {
+///         low: 3,
+///         _: 2,
+///         high: 3
+///     }
+/// }
+///
+/// fn main() {
+///     let template = TestBitfield::new(0);
+///     let low = TestBitfield::low::DESCRIPTOR;
+///     let high: FieldDescriptor<_> = (&template.high).into();
+///
+///     let fields: [&dyn DynField<TestBitfield::TestBitfield>; 2] = [&low, &high];
+///
+///     let mut my_bitfield = TestBitfield::new(0);
+///     for field in fields.iter() {
+///         field.set(&mut my_bitfield, 0b111);
+///     }
+///
+///     assert_eq!(u8::from(my_bitfield), 0b111_00_111);
+///     assert_eq!(fields[1].set_checked(&mut my_bitfield, 0b1000), Err(0b000));
+/// }
+/// ```
+pub trait DynField<B: Bitfield> {
+    /// The field's offset, like [Field::OFFSET].
+    fn offset(&self) -> u8;
+
+    /// The field's size, like [Field::SIZE].
+    fn size(&self) -> u8;
+
+    /// The field's mask, like [Field::MASK].
+    fn mask(&self) -> B::BaseType;
+
+    /// Returns the current value of this field of `bitfield`, like [Field::get].
+    fn get(&self, bitfield: &B) -> B::BaseType {
+        bits::get(*bitfield.raw_ref(), self.offset(), self.mask())
+    }
+
+    /// Sets this field of `bitfield`, like [Field::set]. If the value is wider than the field,
+    /// the value's lowest [Self::size] bits will be used.
+    fn set(&self, bitfield: &mut B, new_value: B::BaseType) {
+        bits::set(bitfield.raw_mut(), self.offset(), self.mask(), new_value)
+    }
+
+    /// Sets this field of `bitfield`, like [Field::set_checked]. If the value is wider than the field,
+    /// returns an `Err` result containing the value's lowest [Self::size] bits and doesn't modify the field.
+    fn set_checked(&self, bitfield: &mut B, new_value: B::BaseType) -> Result<(), B::BaseType> {
+        let masked = new_value & self.mask();
+
+        if masked != new_value {
+            Err(masked)
+        } else {
+            self.set(bitfield, masked);
+
+            Ok(())
+        }
+    }
+}
+
+impl<B: Bitfield> DynField<B> for FieldDescriptor<B> {
+    fn offset(&self) -> u8 { self.offset }
+
+    fn size(&self) -> u8 { self.size }
+
+    fn mask(&self) -> B::BaseType { self.mask }
+}
+
@@ -0,0 +1,47 @@
+//! Fields that are read and written together, see [FieldGroup].
+
+use crate::Bitfield;
+
+/// The trait that's implemented for all field groups, declared with `#[group(...)]` in [bitfield](crate::bitfield).
+///
+/// A group is accessed like a field (`my_bitfield.some_group.get()`), but its value is a tuple
+/// with the values of all its fields, in the order they're listed in the group. A group can have at most 12 fields.
+///
+/// Example:
+/// ```
+/// use simple_bitfield::{ bitfield, Field, FieldGroup };
+///
+/// bitfield! {
+///     #[group(pll: m, n, p)]
+///     struct Clock<u32> {
+///         m: 6,
+///         n: 9,
+///         p: 2,
+///         enable: 1
+///     }
+/// }
+///
+/// fn main() {
+///     let mut clock = Clock::new(0);
+///
+///     clock.pll.set((8, 336, 2));
+///     assert_eq!(clock.pll.get(), (8, 336, 2));
+///     assert_eq!((clock.m.get(), clock.n.get(), clock.p.get()), (8, 336, 2));
+///
+///     assert_eq!(Clock::pll::POSITIONED_MASK, 0x1_FFFF);
+/// }
+/// ```
+pub trait FieldGroup<B: Bitfield> {
+    /// A tuple with one [Bitfield::BaseType] per field of the group.
+    type Values;
+
+    /// The union of the [positioned masks](crate::Field::POSITIONED_MASK) of all fields of the group.
+    const POSITIONED_MASK: B::BaseType;
+
+    /// Returns the current values of all fields of the group.
+    fn get(&self) -> Self::Values;
+
+    /// Sets all fields of the group with a single read-modify-write. If a value is wider than its field,
+    /// the value's lowest bits will be used, like [Field::set](crate::Field::set).
+    fn set(&mut self, values: Self::Values);
+}
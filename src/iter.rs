@@ -0,0 +1,223 @@
+//! Access to all fields of a bitfield at once, see [Bitfield::iter_fields] and [Bitfield::visit_fields],
+//! to the set bits of a field, see [Field::set_bits], and to the fields that differ between two bitfields,
+//! see [Bitfield::diff].
+
+use core::{
+    slice,
+    iter::FusedIterator,
+    fmt::{ self, Display }
+};
+use crate::{ bits, Bitfield, Field, FieldInfo, BitStorage };
+
+/// Iterator over the names and values of a bitfield's named fields, in declaration order.
+/// Created by [Bitfield::iter_fields].
+#[derive(Clone, Debug)]
+pub struct FieldValues<B: Bitfield> {
+    pub(crate) value: B::BaseType,
+    pub(crate) layout: slice::Iter<'static, FieldInfo<B::BaseType>>
+}
+
+impl<B: Bitfield> Iterator for FieldValues<B> {
+    type Item = (&'static str, B::BaseType);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let value = self.value;
+
+        self.layout
+            .find(|field| !field.reserved)
+            .map(|field| (field.name, (value >> field.offset) & field.mask))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        (0, self.layout.size_hint().1)
+    }
+}
+
+/// Visits the named fields of a bitfield with their own types, see [Bitfield::visit_fields].
+///
+/// Example:
+/// ```
+/// use simple_bitfield::{ bitfield, Bitfield, Field, FieldVisitor };
+///
+/// bitfield! {
+///     struct TestBitfield<u8> {
+///         low: 3,
+///         _: 2,
+///         high: 3
+///     }
+/// }
+///
+/// // Sums up the sizes of the fields that aren't zero
+/// struct UsedBits(u8);
+///
+/// impl<B: Bitfield> FieldVisitor<B> for UsedBits {
+///     fn visit<F: Field<B>>(&mut self, _name: &'static str, field: &F) {
+///         if field.is_set() {
+///             self.0 += F::SIZE;
+///         }
+///     }
+/// }
+///
+/// fn main() {
+///     let mut used_bits = UsedBits(0);
+///     TestBitfield::new(0b001_00_000).visit_fields(&mut used_bits);
+///
+///     assert_eq!(used_bits.0, 3);
+/// }
+/// ```
+pub trait FieldVisitor<B: Bitfield> {
+    /// Called for each named field of the bitfield, in declaration order.
+    fn visit<F: Field<B>>(&mut self, name: &'static str, field: &F);
+}
+
+/// Iterator over the positions of the set bits of a field's value, relative to the field's offset, in increasing order.
+/// Created by [Field::set_bits].
+#[derive(Clone, Debug)]
+pub struct SetBits<T: BitStorage> {
+    pub(crate) value: T
+}
+
+impl<T: BitStorage> Iterator for SetBits<T> {
+    type Item = u8;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.value == T::ZERO {
+            return None;
+        }
+
+        let position = self.value.trailing_zeros() as u8;
+        // Clear the lowest set bit
+        self.value = self.value & self.value.wrapping_sub(T::ONE);
+
+        Some(position)
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        let count = self.value.count_ones() as usize;
+
+        (count, Some(count))
+    }
+}
+
+impl<T: BitStorage> ExactSizeIterator for SetBits<T> {}
+
+impl<T: BitStorage> FusedIterator for SetBits<T> {}
+
+/// A field whose value differs between two bitfields, see [Bitfield::diff].
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FieldChange<T> {
+    /// The field's name, `_` for skipped fields and for bits that aren't covered by any field.
+    pub name: &'static str,
+    /// The field's offset.
+    pub offset: u8,
+    /// The field's size.
+    pub size: u8,
+    /// The field's value in the first bitfield.
+    pub old: T,
+    /// The field's value in the second bitfield.
+    pub new: T,
+    /// `true` for skipped fields and bits that aren't covered by any field.
+    pub reserved: bool
+}
+
+impl<T: BitStorage> Display for FieldChange<T> {
+    /// Writes `name: old -> new`. Reserved bits are written as `_ @ offset: old -> new`.
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.reserved {
+            write!(f, "{} @ {}: {} -> {}", self.name, self.offset, self.old, self.new)
+        } else {
+            write!(f, "{}: {} -> {}", self.name, self.old, self.new)
+        }
+    }
+}
+
+/// Iterator over the fields that differ between two bitfields, in declaration order,
+/// followed by the bits that aren't covered by any field. Created by [Bitfield::diff].
+///
+/// Displayed as the list of changes, like `mode: 1 -> 3, enable: 0 -> 1`.
+#[derive(Debug)]
+pub struct FieldChanges<B: Bitfield> {
+    pub(crate) old: B::BaseType,
+    pub(crate) new: B::BaseType,
+    pub(crate) layout: slice::Iter<'static, FieldInfo<B::BaseType>>,
+    /// The bits that aren't covered by any field and haven't been compared yet.
+    pub(crate) uncovered: B::BaseType
+}
+
+// Not derived, because that would require `B: Clone`
+impl<B: Bitfield> Clone for FieldChanges<B> {
+    fn clone(&self) -> Self {
+        FieldChanges { old: self.old, new: self.new, layout: self.layout.clone(), uncovered: self.uncovered }
+    }
+}
+
+impl<B: Bitfield> FieldChanges<B> {
+    pub(crate) fn new(old: B::BaseType, new: B::BaseType) -> Self {
+        let mut uncovered = B::BaseType::ONES;
+        for field in B::LAYOUT {
+            uncovered = uncovered & !(field.mask << field.offset);
+        }
+
+        FieldChanges { old, new, layout: B::LAYOUT.iter(), uncovered }
+    }
+
+    /// Returns the next run of uncovered bits as `(offset, size)`.
+    fn next_uncovered(&mut self) -> Option<(u8, u8)> {
+        if self.uncovered == B::BaseType::ZERO {
+            return None;
+        }
+
+        let offset = self.uncovered.trailing_zeros() as u8;
+        let run = (!(self.uncovered >> offset)).trailing_zeros() as u8;
+        let size = run.min(B::BaseType::BITS - offset);
+
+        self.uncovered = self.uncovered & !(bits::mask::<B::BaseType>(size) << offset);
+
+        Some((offset, size))
+    }
+}
+
+impl<B: Bitfield> Iterator for FieldChanges<B> {
+    type Item = FieldChange<B::BaseType>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        let (old, new) = (self.old, self.new);
+
+        for field in &mut self.layout {
+            let (old_value, new_value) = ((old >> field.offset) & field.mask, (new >> field.offset) & field.mask);
+
+            if old_value != new_value {
+                return Some(FieldChange {
+                    name: field.name, offset: field.offset, size: field.size,
+                    old: old_value, new: new_value, reserved: field.reserved
+                });
+            }
+        }
+
+        while let Some((offset, size)) = self.next_uncovered() {
+            let mask = bits::mask::<B::BaseType>(size);
+            let (old_value, new_value) = ((old >> offset) & mask, (new >> offset) & mask);
+
+            if old_value != new_value {
+                return Some(FieldChange { name: "_", offset, size, old: old_value, new: new_value, reserved: true });
+            }
+        }
+
+        None
+    }
+}
+
+impl<B: Bitfield> FusedIterator for FieldChanges<B> {}
+
+impl<B: Bitfield> Display for FieldChanges<B> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        for (i, change) in self.clone().enumerate() {
+            if i > 0 {
+                write!(f, ", ")?;
+            }
+            write!(f, "{}", change)?;
+        }
+
+        Ok(())
+    }
+}
@@ -0,0 +1,434 @@
+//! Compile-time layout computations used by the [bitfield](crate::bitfield) macro,
+//! and the layout descriptions ([FieldInfo]) it generates.
+//!
+//! Everything in here is evaluated in `const` contexts, so layout errors are reported
+//! at compile-time with messages that name the offending field.
+
+/// How a field of a bitfield can be accessed.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+#[non_exhaustive]
+pub enum Access {
+    /// The field can be read and written (all named fields).
+    ReadWrite,
+    /// The bits can't be accessed by name (skipped fields, `_`).
+    None
+}
+
+/// Describes one entry of a bitfield's layout, see [Bitfield::LAYOUT](crate::Bitfield::LAYOUT).
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub struct FieldInfo<T> {
+    /// The field's name, `"_"` for skipped fields.
+    pub name: &'static str,
+    /// The field's offset from the least significant bit, like [Field::OFFSET](crate::Field::OFFSET).
+    pub offset: u8,
+    /// The field's size in bits, like [Field::SIZE](crate::Field::SIZE).
+    pub size: u8,
+    /// The mask that extracts the lowest [Self::size] bits, like [Field::MASK](crate::Field::MASK).
+    pub mask: T,
+    /// How the field can be accessed.
+    pub access: Access,
+    /// `true` for skipped fields (`_`).
+    pub reserved: bool
+}
+
+/// A field's name, size and explicit offset (if any), as written in [bitfield](crate::bitfield). Used internally.
+#[doc(hidden)]
+pub struct FieldSpec {
+    pub name: &'static str,
+    pub size: u32,
+    pub offset: Option<u32>
+}
+
+impl FieldSpec {
+    /// Checks that the field's size and explicit offset (if any) are neither negative nor too large.
+    pub const fn new(bitfield_name: &str, name: &'static str, size: Width, offset: Option<Width>) -> Self {
+        FieldSpec {
+            name,
+            size: size.to_u32(bitfield_name, name, "size"),
+            offset: match offset {
+                Some(offset) => Some(offset.to_u32(bitfield_name, name, "offset")),
+                None => None
+            }
+        }
+    }
+}
+
+/// The integer types that field sizes and offsets can be written in. Used internally.
+#[doc(hidden)]
+#[diagnostic::on_unimplemented(
+    message = "the size or offset of a bitfield's field must be an integer, not `{Self}`",
+    label = "not an integer"
+)]
+pub trait Integer {
+    const SIGNED: bool;
+}
+
+macro_rules! impl_integer {
+    ($signed:literal: $($type:ty)*) => {
+        $(
+            impl Integer for $type {
+                const SIGNED: bool = $signed;
+            }
+        )*
+    };
+}
+
+impl_integer!(false: u8 u16 u32 u64 u128 usize);
+impl_integer!(true: i8 i16 i32 i64 i128 isize);
+
+/// A field's size or offset as written in [bitfield](crate::bitfield), without truncation. Used internally.
+#[doc(hidden)]
+#[derive(Copy, Clone)]
+pub struct Width {
+    negative: bool,
+    magnitude: u128
+}
+
+impl Width {
+    /// `value` only determines the integer type, `signed` and `unsigned` are `value` converted with `as`.
+    pub const fn new<T: Integer>(_value: &T, signed: i128, unsigned: u128) -> Self {
+        if T::SIGNED {
+            Width { negative: signed < 0, magnitude: signed.unsigned_abs() }
+        } else {
+            Width { negative: false, magnitude: unsigned }
+        }
+    }
+
+    const fn to_u32(self, bitfield_name: &str, name: &str, what: &str) -> u32 {
+        if self.negative || self.magnitude > u32::MAX as u128 {
+            let message = Message::new()
+                .str("field `").str(name).str("` of bitfield `").str(bitfield_name).str("` has ")
+                .str(if self.negative { "a negative " } else { "too large a " }).str(what).str(": ")
+                .str(if self.negative { "-" } else { "" }).wide_num(self.magnitude);
+            panic!("{}", message.as_str())
+        }
+
+        self.magnitude as u32
+    }
+}
+
+/// Computes the offsets of all entries of `spec` at once. Used internally.
+///
+/// Each field starts right after the previous one, unless it has an explicit offset.
+#[doc(hidden)]
+pub const fn field_offsets<const N: usize>(spec: &[FieldSpec]) -> [u32; N] {
+    let mut offsets = [0; N];
+    let mut offset = 0;
+
+    let mut i = 0;
+    while i < N {
+        if let Some(explicit_offset) = spec[i].offset {
+            offset = explicit_offset;
+        }
+
+        offsets[i] = offset;
+        offset = offset.saturating_add(spec[i].size);
+        i += 1;
+    }
+
+    offsets
+}
+
+/// The indices in `spec` of the entries that aren't skipped fields (`_`), in order. Used internally.
+///
+/// The macro knows each named field's position among the named fields, and this maps it to the field's entry.
+#[doc(hidden)]
+pub const fn named_field_indices<const N: usize>(spec: &[FieldSpec]) -> [usize; N] {
+    let mut indices = [0; N];
+    let mut count = 0;
+
+    let mut i = 0;
+    while i < spec.len() {
+        if !str_eq(spec[i].name, "_") {
+            indices[count] = i;
+            count += 1;
+        }
+        i += 1;
+    }
+
+    indices
+}
+
+/// Describes the `index`-th entry of `spec`, whose offset is `offsets[index]` and whose mask is `mask`. Used internally.
+#[doc(hidden)]
+pub const fn field_info<T: Copy>(spec: &[FieldSpec], offsets: &[u32], index: usize, mask: T) -> FieldInfo<T> {
+    let reserved = str_eq(spec[index].name, "_");
+
+    FieldInfo {
+        name: spec[index].name,
+        offset: offsets[index] as u8,
+        size: spec[index].size as u8,
+        mask,
+        access: if reserved { Access::None } else { Access::ReadWrite },
+        reserved
+    }
+}
+
+/// The number of entries of `spec` that aren't skipped fields (`_`). Used internally.
+#[doc(hidden)]
+pub const fn named_field_count(spec: &[FieldSpec]) -> usize {
+    let mut count = 0;
+    let mut i = 0;
+
+    while i < spec.len() {
+        if !str_eq(spec[i].name, "_") {
+            count += 1;
+        }
+        i += 1;
+    }
+
+    count
+}
+
+/// Checks that all fields are non-empty, fit into `max_bits` and don't overlap,
+/// given their offsets computed by [field_offsets]. Used internally.
+/// If `exhaustive`, also checks that the fields (including `_`) cover all `max_bits` bits.
+///
+/// Panics (which is a compile-time error when evaluated in a `const`) with a message
+/// that names the bitfield, the field and the numbers involved.
+#[doc(hidden)]
+pub const fn check_layout(spec: &[FieldSpec], offsets: &[u32], max_bits: u8, exhaustive: bool, bitfield_name: &str, type_name: &str) {
+    let max_bits = max_bits as u32;
+    let total_width = total_width(spec, offsets);
+
+    let mut i = 0;
+    while i < spec.len() {
+        let (offset, size) = (offsets[i], spec[i].size);
+
+        if size == 0 {
+            let message = Message::new()
+                .str("field `").str(spec[i].name).str("` of bitfield `").str(bitfield_name)
+                .str("` has size 0 (at offset ").num(offset).str(")");
+            panic!("{}", message.as_str())
+        }
+
+        if offset.saturating_add(size) > max_bits {
+            let message = Message::new()
+                .str("field `").str(spec[i].name).str("` of bitfield `").str(bitfield_name)
+                .str("` doesn't fit into `").str(type_name)
+                .str("`: its offset is ").num(offset).str(" and its size is ").num(size)
+                .str(", so it ends at bit ").wide_num(offset as u128 + size as u128)
+                .str(", but MAX_BITS is ").num(max_bits)
+                .str(" (total width of all fields: ").wide_num(total_width as u128).str(" bits)");
+            panic!("{}", message.as_str())
+        }
+
+        let mut j = 0;
+        while j < i {
+            let (other_offset, other_size) = (offsets[j], spec[j].size);
+
+            if offset < other_offset + other_size && other_offset < offset + size {
+                let message = Message::new()
+                    .str("fields `").str(spec[j].name).str("` (offset ").num(other_offset).str(", size ").num(other_size)
+                    .str(") and `").str(spec[i].name).str("` (offset ").num(offset).str(", size ").num(size)
+                    .str(") of bitfield `").str(bitfield_name).str("` overlap");
+                panic!("{}", message.as_str())
+            }
+            j += 1;
+        }
+
+        i += 1;
+    }
+
+    if exhaustive {
+        // Fields don't overlap, so the bits they cover are just the sum of their sizes
+        let covered = covered_bits(spec);
+
+        if covered != max_bits {
+            let message = Message::new()
+                .str("bitfield `").str(bitfield_name).str("` is #[exhaustive], but its fields cover ").num(covered)
+                .str(" of the ").num(max_bits).str(" bits of `").str(type_name).str("`: ")
+                .num(max_bits - covered).str(" bits are missing, starting at bit ").num(first_missing_bit(spec, offsets))
+                .str(" (use `_` to skip bits explicitly)");
+            panic!("{}", message.as_str())
+        }
+    }
+}
+
+/// The largest number of fields in a group: the values of a group are a tuple,
+/// and tuples only implement `From`, `Debug`, `PartialEq` etc. for up to 12 elements.
+const MAX_GROUP_FIELDS: usize = 12;
+
+/// Checks that the group called `group_name` has at most [MAX_GROUP_FIELDS] `members`,
+/// that they're all named fields and that none is listed twice. Used internally.
+///
+/// Panics (which is a compile-time error when evaluated in a `const`) with a message that names the group and the field.
+#[doc(hidden)]
+pub const fn check_group(spec: &[FieldSpec], members: &[&str], group_name: &str, bitfield_name: &str) {
+    if members.len() > MAX_GROUP_FIELDS {
+        let message = Message::new()
+            .str("group `").str(group_name).str("` of bitfield `").str(bitfield_name)
+            .str("` has ").num(members.len() as u32).str(" fields, but groups can have at most ")
+            .num(MAX_GROUP_FIELDS as u32).str(" fields");
+        panic!("{}", message.as_str())
+    }
+
+    let mut i = 0;
+    while i < members.len() {
+        let mut found = false;
+
+        let mut j = 0;
+        while j < spec.len() {
+            found = found || str_eq(spec[j].name, members[i]);
+            j += 1;
+        }
+
+        if !found || str_eq(members[i], "_") {
+            let message = Message::new()
+                .str("group `").str(group_name).str("` of bitfield `").str(bitfield_name)
+                .str("` refers to `").str(members[i]).str("`, which isn't a field of it");
+            panic!("{}", message.as_str())
+        }
+
+        let mut j = 0;
+        while j < i {
+            if str_eq(members[i], members[j]) {
+                let message = Message::new()
+                    .str("field `").str(members[i]).str("` is listed twice in group `").str(group_name)
+                    .str("` of bitfield `").str(bitfield_name).str("`");
+                panic!("{}", message.as_str())
+            }
+            j += 1;
+        }
+
+        i += 1;
+    }
+}
+
+/// The number of bits covered by all fields.
+const fn covered_bits(spec: &[FieldSpec]) -> u32 {
+    let mut bits = 0;
+    let mut i = 0;
+
+    while i < spec.len() {
+        bits += spec[i].size;
+        i += 1;
+    }
+
+    bits
+}
+
+/// The lowest bit that isn't covered by any field.
+const fn first_missing_bit(spec: &[FieldSpec], offsets: &[u32]) -> u32 {
+    let mut bit = 0;
+
+    'bits: loop {
+        let mut i = 0;
+        while i < spec.len() {
+            let offset = offsets[i];
+
+            if offset <= bit && bit < offset + spec[i].size {
+                bit += 1;
+                continue 'bits;
+            }
+            i += 1;
+        }
+
+        return bit;
+    }
+}
+
+/// The number of bits up to the end of the field that ends last.
+const fn total_width(spec: &[FieldSpec], offsets: &[u32]) -> u64 {
+    let mut width = 0;
+    let mut i = 0;
+
+    while i < spec.len() {
+        let end = offsets[i] as u64 + spec[i].size as u64;
+        if end > width {
+            width = end;
+        }
+        i += 1;
+    }
+
+    width
+}
+
+/// `str` comparison that can be used in `const` contexts.
+const fn str_eq(a: &str, b: &str) -> bool {
+    let (a, b) = (a.as_bytes(), b.as_bytes());
+
+    if a.len() != b.len() {
+        return false;
+    }
+
+    let mut i = 0;
+    while i < a.len() {
+        if a[i] != b[i] {
+            return false;
+        }
+        i += 1;
+    }
+
+    true
+}
+
+/// Fixed-size string builder for compile-time error messages.
+struct Message {
+    bytes: [u8; Message::CAPACITY],
+    len: usize
+}
+
+impl Message {
+    const CAPACITY: usize = 512;
+
+    const fn new() -> Self {
+        Message { bytes: [0; Message::CAPACITY], len: 0 }
+    }
+
+    /// Appends `s` if it fits as a whole, so the message always stays valid UTF-8.
+    const fn str(mut self, s: &str) -> Self {
+        let s = s.as_bytes();
+
+        if self.len + s.len() > Message::CAPACITY {
+            return self;
+        }
+
+        let mut i = 0;
+        while i < s.len() {
+            self.bytes[self.len] = s[i];
+            self.len += 1;
+            i += 1;
+        }
+
+        self
+    }
+
+    const fn num(self, n: u32) -> Self {
+        self.wide_num(n as u128)
+    }
+
+    const fn wide_num(mut self, mut n: u128) -> Self {
+        let mut digits = [0u8; 39];
+        let mut count = 0;
+
+        loop {
+            digits[count] = b'0' + (n % 10) as u8;
+            count += 1;
+            n /= 10;
+
+            if n == 0 {
+                break;
+            }
+        }
+
+        if self.len + count > Message::CAPACITY {
+            return self;
+        }
+
+        while count > 0 {
+            count -= 1;
+            self.bytes[self.len] = digits[count];
+            self.len += 1;
+        }
+
+        self
+    }
+
+    const fn as_str(&self) -> &str {
+        match core::str::from_utf8(self.bytes.split_at(self.len).0) {
+            Ok(message) => message,
+            Err(_) => "invalid bitfield layout"
+        }
+    }
+}
@@ -10,7 +10,11 @@
 //!  * their fields can be accessed by name (`my_bitfield.some_field`) which aids readability;
 //!  * each field has the same set of functions (`get`, `set`, `set_checked` and more);
 //!  * each field has its own distinct type;
-//!  * it's possible to skip (and not name) any number of bits
+//!  * each field has its own visibility (`pub`, `pub(crate)` or private, just like struct fields);
+//!  * it's possible to skip (and not name) any number of bits;
+//!  * their layout can be inspected at runtime via [Bitfield::LAYOUT], and their fields
+//!    can be enumerated via [Bitfield::iter_fields] and [Bitfield::visit_fields];
+//!  * the fields that differ between two bitfields can be listed via [Bitfield::diff]
 //!
 //! The [bitfield] macro was inspired by [https://guiand.xyz/blog-posts/bitfields.html](https://guiand.xyz/blog-posts/bitfields.html).
 //! 
@@ -82,33 +86,379 @@
 //!
 //! The [TestBitfield] module is only present in the documentation and shows how a bitfield is structured internally.
 
-use core::{
-    ops::{ Shl, Shr, BitAnd, BitOrAssign, BitXorAssign },
-    fmt::{ Debug, Display }
-};
+mod bits;
+mod layout;
+mod iter;
+mod dynamic;
+mod group;
+mod builder;
+mod typed;
+mod masked;
 
+pub use bits::{ BitStorage, BitRange };
+
+pub use layout::{ FieldInfo, Access };
+pub use iter::{ FieldValues, FieldVisitor, SetBits, FieldChange, FieldChanges };
+pub use dynamic::{ FieldError, FieldDescriptor, DynField };
+pub use group::FieldGroup;
+pub use builder::{ Unset, Set };
+pub use typed::{ TypedField, FieldValue };
+pub use masked::{ FieldSet, NamedFields, Masked };
+
+#[doc(hidden)]
+pub use layout::{ FieldSpec, Integer, Width, field_offsets, named_field_indices, field_info, named_field_count, check_layout, check_group };
+
+// Names the fields' `set_field` and `with_field` methods
 #[doc(hidden)]
-pub use static_assertions::const_assert;
+pub use paste::paste as __paste;
 
-pub trait Bitfield {
+pub trait Bitfield: Sized {
     //! The trait that's implemented for all bitfields.
-    //! Used mainly to access the bitfield's underlying type, [Self::BaseType].
+    //! Gives access to the bitfield's underlying type, [Self::BaseType], its raw value and its layout,
+    //! so that code can be generic over all bitfields.
 
     /// The bitfield's underlying type.
-    type BaseType: Copy + Debug + Display;
+    type BaseType: BitStorage;
     
     /// The maximum number of bits that the bitfield can hold.
     /// Used for compile-time checking that no newly added field requires a [Self::BaseType] wider than this.
     const MAX_BITS: u8 = 8 * core::mem::size_of::<Self::BaseType>() as u8;
+
+    /// The bitfield's name, as written in [bitfield].
+    const NAME: &'static str;
+
+    /// The number of named fields (skipped fields, `_`, aren't counted).
+    const FIELD_COUNT: usize;
+
+    /// The bits covered by named fields.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield };
+    ///
+    /// bitfield! {
+    ///     struct TestBitfield<u8> {
+    ///         low: 3,
+    ///         _: 2,
+    ///         high: 2
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     type Bf = TestBitfield::TestBitfield;
+    ///
+    ///     assert_eq!((Bf::NAME, Bf::FIELD_COUNT), ("TestBitfield", 2));
+    ///     assert_eq!(Bf::USED_MASK, 0b0110_0111);
+    ///     assert_eq!(Bf::UNUSED_MASK, 0b1001_1000);
+    /// }
+    /// ```
+    const USED_MASK: Self::BaseType;
+
+    /// Every bit not covered by a named field: skipped fields (`_`), gaps before explicitly offset fields and the bits after the last field.
+    /// The complement of [Self::USED_MASK].
+    const UNUSED_MASK: Self::BaseType;
+
+    /// The raw value in which every named field has its [default value](Field::DEFAULT) and all other bits are zero.
+    const DEFAULTS: Self::BaseType;
+
+    /// Creates a bitfield from its raw value, like `From<BaseType>`.
+    fn from_raw(raw: Self::BaseType) -> Self;
+
+    /// Returns the bitfield's raw value, like `Into<BaseType>`.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield, Field };
+    ///
+    /// bitfield! {
+    ///     struct TestBitfield<u8> {
+    ///         low: 4,
+    ///         high: 4
+    ///     }
+    /// }
+    ///
+    /// // Works for any bitfield
+    /// fn swap_raw<B: Bitfield>(a: B, b: B) -> (B, B) {
+    ///     (B::from_raw(b.into_raw()), B::from_raw(a.into_raw()))
+    /// }
+    ///
+    /// fn main() {
+    ///     let (mut a, b) = swap_raw(TestBitfield::new(0x12), TestBitfield::new(0x34));
+    ///     assert_eq!((a.high.get(), b.high.get()), (3, 1));
+    ///
+    ///     *a.raw_mut() = 0xAB;
+    ///     assert_eq!((*a.raw_ref(), a.low.get()), (0xAB, 0xB));
+    /// }
+    /// ```
+    fn into_raw(self) -> Self::BaseType;
+
+    /// Returns a reference to the bitfield's raw value.
+    fn raw_ref(&self) -> &Self::BaseType;
+
+    /// Returns a mutable reference to the bitfield's raw value.
+    fn raw_mut(&mut self) -> &mut Self::BaseType;
+
+    /// Describes all fields of the bitfield (including skipped ones, `_`) in the order they were declared.
+    /// Lets generic code inspect any bitfield's layout:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield };
+    ///
+    /// bitfield! {
+    ///     struct TestBitfield<u16> {
+    ///         low: 4,
+    ///         _: 4,
+    ///         high: 8
+    ///     }
+    /// }
+    ///
+    /// fn named_fields<B: Bitfield>() -> usize {
+    ///     B::LAYOUT.iter().filter(|field| !field.reserved).count()
+    /// }
+    ///
+    /// fn main() {
+    ///     let layout = TestBitfield::TestBitfield::LAYOUT;
+    ///
+    ///     assert_eq!(layout.len(), 3);
+    ///     assert_eq!((layout[2].name, layout[2].offset, layout[2].size, layout[2].mask), ("high", 8, 8, 0xFF));
+    ///     assert!(layout[1].reserved);
+    ///     assert_eq!(named_fields::<TestBitfield::TestBitfield>(), 2);
+    /// }
+    /// ```
+    const LAYOUT: &'static [FieldInfo<Self::BaseType>];
+
+    /// Returns an iterator over the names and current values of all named fields, in declaration order.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield };
+    ///
+    /// bitfield! {
+    ///     struct TestBitfield<u8> {
+    ///         low: 3,
+    ///         _: 2,
+    ///         high: 3
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let my_bitfield = TestBitfield::new(0b101_00_011);
+    ///     let mut fields = my_bitfield.iter_fields();
+    ///
+    ///     assert_eq!(fields.next(), Some(("low", 0b011)));
+    ///     assert_eq!(fields.next(), Some(("high", 0b101)));
+    ///     assert_eq!(fields.next(), None);
+    /// }
+    /// ```
+    fn iter_fields(&self) -> FieldValues<Self> {
+        FieldValues { value: *self.raw_ref(), layout: Self::LAYOUT.iter() }
+    }
+
+    /// Calls `visitor.visit` for each named field, in declaration order.
+    /// Unlike [Self::iter_fields], the visitor gets each field with its own type, see [FieldVisitor].
+    fn visit_fields<V: FieldVisitor<Self>>(&self, visitor: &mut V);
+
+    /// Returns the current value of the named field called `name`, or `None` if there's no such field.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield };
+    ///
+    /// bitfield! {
+    ///     struct TestBitfield<u8> {
+    ///         low: 3,
+    ///         _: 2,
+    ///         high: 3
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let my_bitfield = TestBitfield::new(0b101_00_011);
+    ///
+    ///     assert_eq!(my_bitfield.get_by_name("high"), Some(0b101));
+    ///     assert_eq!(my_bitfield.get_by_name("middle"), None);
+    ///     assert_eq!(my_bitfield.get_by_name("_"), None); // Skipped fields have no name
+    /// }
+    /// ```
+    fn get_by_name(&self, name: &str) -> Option<Self::BaseType> {
+        self.iter_fields().find(|&(field_name, _)| field_name == name).map(|(_, value)| value)
+    }
+
+    /// Sets the named field called `name` to `new_value`.
+    ///
+    /// Returns an error and doesn't modify the bitfield if there's no such field
+    /// or if the value is wider than the field (like [Field::set_checked]).
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield, FieldError };
+    ///
+    /// bitfield! {
+    ///     struct TestBitfield<u8> {
+    ///         low: 3,
+    ///         _: 2,
+    ///         high: 3
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut my_bitfield = TestBitfield::new(0);
+    ///
+    ///     assert_eq!(my_bitfield.set_by_name("high", 0b110), Ok(()));
+    ///     assert_eq!(my_bitfield.set_by_name("high", 0b1000), Err(FieldError::ValueTooWide { size: 3 }));
+    ///     assert_eq!(my_bitfield.set_by_name("middle", 1), Err(FieldError::UnknownField));
+    ///
+    ///     assert_eq!(u8::from(my_bitfield), 0b110_00_000);
+    /// }
+    /// ```
+    fn set_by_name(&mut self, name: &str, new_value: Self::BaseType) -> Result<(), FieldError> {
+        let field = Self::LAYOUT.iter()
+            .find(|field| !field.reserved && field.name == name)
+            .ok_or(FieldError::UnknownField)?;
+
+        FieldDescriptor::<Self>::new(field.offset, field.size, field.mask)
+            .set_checked(self, new_value)
+            .map_err(|_| FieldError::ValueTooWide { size: field.size })
+    }
+
+    /// Returns the current value of the field `F`, like `my_bitfield.f.get()`.
+    ///
+    /// Since the field is a type parameter, this works in generic code:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield, Field };
+    ///
+    /// bitfield! {
+    ///     struct TestBitfield<u8> {
+    ///         low: 3,
+    ///         _: 2,
+    ///         high: 3
+    ///     }
+    /// }
+    ///
+    /// // Sets all bits of the field `F` of any bitfield
+    /// fn fill<B: Bitfield, F: Field<B>>(bitfield: &mut B) {
+    ///     bitfield.set::<F>(F::MASK)
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut my_bitfield = TestBitfield::new(0);
+    ///     fill::<_, TestBitfield::high>(&mut my_bitfield);
+    ///
+    ///     assert_eq!(my_bitfield.get::<TestBitfield::high>(), 0b111);
+    ///     assert_eq!(my_bitfield.get::<TestBitfield::low>(), 0);
+    /// }
+    /// ```
+    fn get<F: Field<Self>>(&self) -> Self::BaseType {
+        DynField::get(&F::DESCRIPTOR, self)
+    }
+
+    /// Sets the value of the field `F`, like `my_bitfield.f.set(new_value)`.
+    /// If the value is wider than the field, the value's lowest [Field::SIZE] bits will be used.
+    fn set<F: Field<Self>>(&mut self, new_value: Self::BaseType) {
+        DynField::set(&F::DESCRIPTOR, self, new_value)
+    }
+
+    /// Returns a copy of the bitfield with the field `F` set to `new_value`, like [Self::set].
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield };
+    ///
+    /// bitfield! {
+    ///     struct TestBitfield<u8> {
+    ///         low: 3,
+    ///         _: 2,
+    ///         high: 3
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let my_bitfield = TestBitfield::new(0)
+    ///         .with::<TestBitfield::low>(0b101)
+    ///         .with::<TestBitfield::high>(0b011);
+    ///
+    ///     assert_eq!(u8::from(my_bitfield), 0b011_00_101);
+    /// }
+    /// ```
+    fn with<F: Field<Self>>(mut self, new_value: Self::BaseType) -> Self {
+        self.set::<F>(new_value);
+        self
+    }
+
+    /// Returns `true` if the bits in `mask` are the same in both bitfields. The other bits are ignored.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield, mask_of };
+    ///
+    /// bitfield! {
+    ///     struct Status<u8> {
+    ///         ready: 1,
+    ///         error: 1,
+    ///         _: 2,
+    ///         counter: 4
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let a = Status::new(0b0001_00_01);
+    ///     let b = Status::new(0b0111_11_01);
+    ///
+    ///     assert!(a.eq_masked(&b, mask_of!(Status: ready | error)));
+    ///     assert!(!a.eq_fields(&b)); // `counter` differs
+    ///     assert!(a.eq_fields(&Status::new(0b0001_11_01))); // Only the skipped bits differ
+    /// }
+    /// ```
+    fn eq_masked(&self, other: &Self, mask: Self::BaseType) -> bool {
+        *self.raw_ref() & mask == *other.raw_ref() & mask
+    }
+
+    /// Returns `true` if all named fields are the same in both bitfields.
+    /// Every bit not covered by a named field is ignored, see [Self::USED_MASK].
+    fn eq_fields(&self, other: &Self) -> bool {
+        self.eq_masked(other, Self::USED_MASK)
+    }
+
+    /// Returns an iterator over the fields whose values differ between `self` (the old value) and `other` (the new value),
+    /// in declaration order. Changes of skipped fields (`_`) and of the bits that aren't covered by any field are reported too,
+    /// as [reserved](FieldChange::reserved) changes.
+    ///
+    /// The iterator is displayed as the list of changes.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Bitfield, FieldChange };
+    ///
+    /// bitfield! {
+    ///     struct Control<u8> {
+    ///         enable: 1,
+    ///         mode: 2,
+    ///         _: 2,
+    ///         level: 2
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let before = Control::new(0b0_00_00_01_0);
+    ///     let after = Control::new(0b0_00_00_11_1);
+    ///
+    ///     let mut changes = before.diff(&after);
+    ///     assert_eq!(changes.to_string(), "enable: 0 -> 1, mode: 1 -> 3");
+    ///
+    ///     let first = changes.next().unwrap();
+    ///     assert_eq!((first.name, first.old, first.new, first.reserved), ("enable", 0, 1, false));
+    ///
+    ///     // The skipped bits and the last bit, which doesn't belong to any field
+    ///     let reserved = Control::new(0b1_01_10_01_0);
+    ///     assert_eq!(before.diff(&reserved).to_string(), "_ @ 3: 0 -> 2, level: 0 -> 1, _ @ 7: 0 -> 1");
+    ///     assert!(before.diff(&before).next().is_none());
+    /// }
+    /// ```
+    fn diff(&self, other: &Self) -> FieldChanges<Self> {
+        FieldChanges::new(*self.raw_ref(), *other.raw_ref())
+    }
 }
 
-pub trait Field<B: Bitfield>
-    where B::BaseType:
-        Shl<u8, Output=B::BaseType> +
-        Shr<u8, Output=B::BaseType> +
-        BitAnd<Output=B::BaseType> +
-        BitOrAssign + BitXorAssign + PartialEq
-{
+pub trait Field<B: Bitfield> {
     //! The trait that's implemented for all fields of all bitfields.
     //! Allows the nice `my_bitfield.some_field.get()` syntax.
 
@@ -143,12 +493,42 @@ pub trait Field<B: Bitfield>
     /// }
     /// ```
     const MASK: B::BaseType;
+
+    /// [Self::MASK] shifted into the field's position, so that it extracts the field's bits
+    /// from the bitfield's raw value. Computed automatically.
+    ///
+    /// Example usage:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Field };
+    ///
+    /// bitfield! {
+    ///     struct TestBitfield<u32> {
+    ///         _: 4,
+    ///         field1: 4
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     assert_eq!(TestBitfield::field1::POSITIONED_MASK, 0b1111_0000);
+    /// }
+    /// ```
+    const POSITIONED_MASK: B::BaseType;
+
+    /// The field's default value, given with the `#[default(value)]` attribute in [bitfield]. Zero by default.
+    /// Used by [from_fields] for the fields that aren't listed.
+    const DEFAULT: B::BaseType;
     
     /// Returns `true` if the field is not equal to zero.
     fn is_set(&self) -> bool;
     
-    /// `true` if the field is within the bitfield's bounds. Used for compile-time checking.
+    /// `true` if the field is within the bitfield's bounds.
+    ///
+    /// [bitfield] checks this at compile-time for every field and reports the offending field's offset and size if it fails.
     const VALID: bool = Self::SIZE + Self::OFFSET <= B::MAX_BITS;
+
+    /// Describes the field at runtime, so that it can be stored and passed around as a value.
+    /// See [FieldDescriptor].
+    const DESCRIPTOR: FieldDescriptor<B> = FieldDescriptor::new(Self::OFFSET, Self::SIZE, Self::MASK);
     
     /// Returns the size of a field _at runtime_, while [Self::SIZE] is used on the _type_ of the field at compile-time.
     fn size(&self) -> u8 { Self::SIZE }
@@ -158,6 +538,9 @@ pub trait Field<B: Bitfield>
 
     /// Returns the mask of a field _at runtime_, while [Self::MASK] is used on the _type_ of the field at compile-time.
     fn mask(&self) -> B::BaseType { Self::MASK }
+
+    /// Returns the descriptor of a field _at runtime_, while [Self::DESCRIPTOR] is used on the _type_ of the field at compile-time.
+    fn descriptor(&self) -> FieldDescriptor<B> { Self::DESCRIPTOR }
     
     /// Returns the current value of the field.
     ///
@@ -180,7 +563,7 @@ pub trait Field<B: Bitfield>
     fn get(&self) -> B::BaseType {
         let data_ptr: *const B::BaseType = self as *const Self as *const B::BaseType;
         
-        (unsafe { *data_ptr } >> Self::OFFSET) & Self::MASK
+        bits::get(unsafe { *data_ptr }, Self::OFFSET, Self::MASK)
     }
     
     /// Sets the value of a field. If the value is wider than the field,
@@ -206,12 +589,7 @@ pub trait Field<B: Bitfield>
     fn set(&mut self, new_value: B::BaseType) {
         let data_ptr: *mut B::BaseType = self as *const Self as *mut B::BaseType;
         
-        let old_value: B::BaseType = self.get() << Self::OFFSET;
-        
-        unsafe {
-            *data_ptr ^= old_value;
-            *data_ptr |= (new_value & Self::MASK) << Self::OFFSET
-        }
+        bits::set(unsafe { &mut *data_ptr }, Self::OFFSET, Self::MASK, new_value)
     }
 
     /// Sets the value of a field. If the value is wider than the field,
@@ -251,89 +629,737 @@ pub trait Field<B: Bitfield>
             Ok(())
         }
     }
-}
 
-/// Generates the format args for all fields of a bitfield.
-///
-/// The result looks like this: `field_low: value, field: value, field_high: value`. Used internally.
-#[macro_export]
-#[doc(hidden)]
-macro_rules! gen_format_debug {
-    ($self:ident) => { format_args!("{}", "") };
-    ($self:ident | $first_field:ident) => {
-        format_args!(
-            "{}: {:?}",
-            // Can't just refer to `self` because it's a keyword?!
-            // So have to pass it from call site
-            stringify!($first_field), $self.$first_field.get()
-        )
-    };
-    ($self:ident | $first_field:ident | $second_field:ident $(| $other_field:ident)*) => {
-        format_args!(
-            "{}: {:?}, {}",
-            stringify!($first_field), $self.$first_field.get(),
-            $crate::gen_format_debug!($self | $second_field $(| $other_field)*)
-        )
-    };
-}
+    /// Adds `rhs` to the field, wrapping around at the field's [Self::SIZE] bits
+    /// (like `u8::wrapping_add` wraps around at 8 bits). Returns the new value.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Field };
+    ///
+    /// bitfield! {
+    ///     struct Ring<u8> {
+    ///         head: 3,
+    ///         tail: 3
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut ring = Ring::new(0b000_110);
+    ///
+    ///     assert_eq!(ring.head.wrapping_add(3), 0b001);
+    ///     assert_eq!(ring.tail.wrapping_sub(1), 0b111);
+    ///     assert_eq!(u8::from(ring), 0b111_001);
+    /// }
+    /// ```
+    fn wrapping_add(&mut self, rhs: B::BaseType) -> B::BaseType {
+        let new_value = self.get().wrapping_add(rhs) & Self::MASK;
 
+        self.set(new_value);
+        new_value
+    }
 
-/// Creates bitfield types.
-///
-/// Adapted from [https://guiand.xyz/blog-posts/bitfields.html](https://guiand.xyz/blog-posts/bitfields.html)
-///
-/// Example:
-/// ```
-/// use core::mem::{size_of, size_of_val};
-/// use simple_bitfield::{
-///     bitfield, Field // For field access
-/// };
-///
-/// bitfield!{
-///     struct BitfieldName<u8> {
-///         first_two_bits: 2,
-///         three_more_bits: 3
-///     }
-///
-///     struct AnotherBitfield<u32> {
-///         _: 31, // Skip first 31 bits
-///         last_bit: 1
-///     }
-/// }
-///
-/// # pub fn main() {
-/// let value: u8 = 0b111_011_10;
-/// let my_bitfield: BitfieldName::BitfieldName = BitfieldName::new(value);
-/// let another_bitfield = AnotherBitfield::new(value.into());
-///
-/// assert_eq!(
-///     size_of_val(&my_bitfield),
-///     size_of::<u8>()
-/// );
-/// assert_eq!(
-///     size_of_val(&my_bitfield),
-///     size_of::<u8>()
-/// );
-///
-/// assert_eq!(my_bitfield.first_two_bits.size(), 2);
-/// assert_eq!(my_bitfield.three_more_bits.size(), 3);
-///
-/// assert_eq!(my_bitfield.first_two_bits.get(), value & 0b11);
-/// assert_eq!(
-///     my_bitfield.three_more_bits.get(),
-///     (value >> my_bitfield.first_two_bits.size()) & 0b111
-/// );
-///
-/// assert_eq!(another_bitfield.last_bit.get(), 0);
-/// # }
-/// ```
-///
+    /// Subtracts `rhs` from the field, wrapping around at the field's [Self::SIZE] bits. Returns the new value.
+    fn wrapping_sub(&mut self, rhs: B::BaseType) -> B::BaseType {
+        let new_value = self.get().wrapping_sub(rhs) & Self::MASK;
+
+        self.set(new_value);
+        new_value
+    }
+
+    /// Adds `rhs` to the field. Returns the new value,
+    /// or `None` and doesn't modify the field if the result doesn't fit into the field's [Self::SIZE] bits.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Field };
+    ///
+    /// bitfield! {
+    ///     struct Counters<u8> {
+    ///         retries: 2,
+    ///         errors: 6
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut counters = Counters::new(0b000000_10);
+    ///
+    ///     assert_eq!(counters.retries.checked_add(1), Some(0b11));
+    ///     assert_eq!(counters.retries.checked_add(1), None);
+    ///     assert_eq!(counters.errors.checked_sub(1), None);
+    ///
+    ///     assert_eq!(counters.retries.saturating_add(5), 0b11);
+    ///     assert_eq!(counters.errors.saturating_sub(5), 0);
+    /// }
+    /// ```
+    fn checked_add(&mut self, rhs: B::BaseType) -> Option<B::BaseType> {
+        let new_value = self.get().checked_add(rhs).filter(|&value| value & Self::MASK == value)?;
+
+        self.set(new_value);
+        Some(new_value)
+    }
+
+    /// Subtracts `rhs` from the field. Returns the new value,
+    /// or `None` and doesn't modify the field if the result would be negative.
+    fn checked_sub(&mut self, rhs: B::BaseType) -> Option<B::BaseType> {
+        let new_value = self.get().checked_sub(rhs).filter(|&value| value & Self::MASK == value)?;
+
+        self.set(new_value);
+        Some(new_value)
+    }
+
+    /// Adds `rhs` to the field, stopping at the field's largest value ([Self::MASK]). Returns the new value.
+    fn saturating_add(&mut self, rhs: B::BaseType) -> B::BaseType {
+        self.checked_add(rhs).unwrap_or_else(|| {
+            self.set(Self::MASK);
+            Self::MASK
+        })
+    }
+
+    /// Subtracts `rhs` from the field, stopping at zero. Returns the new value.
+    fn saturating_sub(&mut self, rhs: B::BaseType) -> B::BaseType {
+        self.checked_sub(rhs).unwrap_or_else(|| {
+            self.set(B::BaseType::ZERO);
+            B::BaseType::ZERO
+        })
+    }
+
+    /// Adds one to the field, wrapping around to zero after the field's largest value, see [Self::wrapping_add].
+    /// Returns the new value.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Field };
+    ///
+    /// bitfield! {
+    ///     struct Packet<u8> {
+    ///         sequence: 3,
+    ///         kind: 5
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut packet = Packet::new(0b00001_110);
+    ///
+    ///     assert_eq!(packet.sequence.increment(), 0b111);
+    ///     assert_eq!(packet.sequence.increment(), 0b000);
+    ///     assert_eq!(packet.sequence.decrement(), 0b111);
+    ///     assert_eq!(packet.kind.get(), 0b00001);
+    /// }
+    /// ```
+    fn increment(&mut self) -> B::BaseType {
+        self.wrapping_add(B::BaseType::ONE)
+    }
+
+    /// Subtracts one from the field, wrapping around to the field's largest value after zero, see [Self::wrapping_sub].
+    /// Returns the new value.
+    fn decrement(&mut self) -> B::BaseType {
+        self.wrapping_sub(B::BaseType::ONE)
+    }
+
+    /// Returns the number of ones in the field.
+    ///
+    /// Example:
+    /// ```
+    /// use simple_bitfield::{ bitfield, Field };
+    ///
+    /// bitfield! {
+    ///     struct Interrupts<u16> {
+    ///         priority: 4,
+    ///         pending: 8,
+    ///         _: 4
+    ///     }
+    /// }
+    ///
+    /// fn main() {
+    ///     let mut interrupts = Interrupts::new(0b1111_00101000_1111);
+    ///
+    ///     assert_eq!(interrupts.pending.count_ones(), 2);
+    ///     assert_eq!(interrupts.pending.leading_zeros(), 2);
+    ///     assert_eq!(interrupts.pending.trailing_zeros(), 3);
+    ///     assert!(interrupts.pending.set_bits().eq([3, 5]));
+    ///
+    ///     assert_eq!(interrupts.pending.toggle_all(), 0b11010111);
+    ///     assert_eq!(interrupts.pending.reverse_bits(), 0b11101011);
+    ///     assert_eq!(u16::from(interrupts), 0b1111_11101011_1111);
+    /// }
+    /// ```
+    fn count_ones(&self) -> u8 {
+        self.get().count_ones() as u8
+    }
+
+    /// Returns the number of zeros before the field's highest set bit, [Self::SIZE] if the field is zero.
+    fn leading_zeros(&self) -> u8 {
+        // The field's value is in the lowest `SIZE` bits, so the bits above them are always zero
+        (self.get().leading_zeros() - (B::BaseType::BITS - Self::SIZE) as u32) as u8
+    }
+
+    /// Returns the number of zeros after the field's lowest set bit, [Self::SIZE] if the field is zero.
+    fn trailing_zeros(&self) -> u8 {
+        let value = self.get();
+
+        if value == B::BaseType::ZERO { Self::SIZE } else { value.trailing_zeros() as u8 }
+    }
+
+    /// Reverses the order of the field's bits, leaving the other bits of the bitfield as they are.
+    /// Returns the new value.
+    fn reverse_bits(&mut self) -> B::BaseType {
+        let new_value = (self.get().reverse_bits() >> (B::BaseType::BITS - Self::SIZE)) & Self::MASK;
+
+        self.set(new_value);
+        new_value
+    }
+
+    /// Inverts all bits of the field, leaving the other bits of the bitfield as they are. Returns the new value.
+    fn toggle_all(&mut self) -> B::BaseType {
+        let new_value = !self.get() & Self::MASK;
+
+        self.set(new_value);
+        new_value
+    }
+
+    /// Returns an iterator over the positions of the field's set bits, relative to its [Self::OFFSET],
+    /// in increasing order.
+    fn set_bits(&self) -> SetBits<B::BaseType> {
+        SetBits { value: self.get() }
+    }
+}
+
+/// Creates bitfield types.
+///
+/// Adapted from [https://guiand.xyz/blog-posts/bitfields.html](https://guiand.xyz/blog-posts/bitfields.html)
+///
+/// Example:
+/// ```
+/// use core::mem::{size_of, size_of_val};
+/// use simple_bitfield::{
+///     bitfield, Field // For field access
+/// };
+///
+/// bitfield!{
+///     struct BitfieldName<u8> {
+///         first_two_bits: 2,
+///         three_more_bits: 3
+///     }
+///
+///     struct AnotherBitfield<u32> {
+///         _: 31, // Skip first 31 bits
+///         last_bit: 1
+///     }
+/// }
+///
+/// # pub fn main() {
+/// let value: u8 = 0b111_011_10;
+/// let my_bitfield: BitfieldName::BitfieldName = BitfieldName::new(value);
+/// let another_bitfield = AnotherBitfield::new(value.into());
+///
+/// assert_eq!(
+///     size_of_val(&my_bitfield),
+///     size_of::<u8>()
+/// );
+/// assert_eq!(
+///     size_of_val(&my_bitfield),
+///     size_of::<u8>()
+/// );
+///
+/// assert_eq!(my_bitfield.first_two_bits.size(), 2);
+/// assert_eq!(my_bitfield.three_more_bits.size(), 3);
+///
+/// assert_eq!(my_bitfield.first_two_bits.get(), value & 0b11);
+/// assert_eq!(
+///     my_bitfield.three_more_bits.get(),
+///     (value >> my_bitfield.first_two_bits.size()) & 0b111
+/// );
+///
+/// assert_eq!(another_bitfield.last_bit.get(), 0);
+/// # }
+/// ```
+///
 /// The bitfield `BitfieldName` is actually a module. The type that holds the data is `BitfieldName::BitfieldName`,
 /// which is unique for each bitfield. Each field is a zero-size struct that cannot be instantiated separately from the bitfield.
 /// The memory representation of the bitfield is exactly the same as that of the underlying type.
+///
+/// The fields are processed without recursion, so even bitfields with hundreds of fields
+/// don't require raising the `recursion_limit`.
+///
+/// ## Sizes and offsets
+/// A field's size can be any constant expression, not just a literal. Fields are placed one after another,
+/// but a field can also be given an explicit offset with `field @ offset: size`, where `offset` is a literal,
+/// a constant or a parenthesized constant expression. The fields after it continue from its end.
+/// Sizes, offsets and default values are evaluated where `bitfield!` is invoked, so they can use anything in scope there.
+/// Sizes and offsets can be of any integer type and are never truncated: negative values and values that don't fit
+/// into `u32` are compile errors naming the field, and other types (like floats) are type errors.
+/// The compile-time checks that all fields fit into the underlying type apply to the computed values:
+///
+/// ```
+/// use simple_bitfield::{ bitfield, Field };
+///
+/// const ADDR_BITS: u8 = 20;
+/// const FLAG_BITS: u8 = 2;
+///
+/// bitfield!{
+///     struct Descriptor<u32> {
+///         flags: FLAG_BITS,
+///         addr: ADDR_BITS,
+///         tag: 32 - ADDR_BITS - FLAG_BITS
+///     }
+///
+///     struct Sparse<u16> {
+///         low: 4,
+///         high @ (16 - 4): 4
+///     }
+/// }
+///
+/// # fn main() {
+/// assert_eq!(Descriptor::tag::OFFSET, 22);
+/// assert_eq!(Descriptor::tag::SIZE, 10);
+/// assert_eq!(Sparse::high::OFFSET, 12);
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// const ADDR_BITS: u8 = 30;
+///
+/// bitfield!{
+///     // error: field `addr` of bitfield `Descriptor` doesn't fit into `u32`: its offset is 4 and its size is 30,
+///     // so it ends at bit 34, but MAX_BITS is 32 (total width of all fields: 34 bits)
+///     struct Descriptor<u32> {
+///         flags: 4,
+///         addr: ADDR_BITS
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// const FLAG_BITS: i32 = 4 - 8;
+///
+/// bitfield!{
+///     // error: field `flags` of bitfield `Descriptor` has a negative size: -4
+///     struct Descriptor<u32> {
+///         flags: FLAG_BITS,
+///         addr: 20
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ## Compile-time checks
+/// The layout of every bitfield is checked at compile-time. Fields that don't fit into the underlying type,
+/// fields that overlap and fields of size zero are errors whose messages name the field and its offset and size:
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     // error: fields `low` (offset 0, size 8) and `high` (offset 4, size 8) of bitfield `Overlapping` overlap
+///     struct Overlapping<u16> {
+///         low: 8,
+///         high @ 4: 8
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     // error: field `empty` of bitfield `WithEmptyField` has size 0 (at offset 2)
+///     struct WithEmptyField<u8> {
+///         low: 2,
+///         empty: 0
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ## Exhaustive bitfields
+/// Bitfields marked `#[exhaustive]` must account for every bit of the base type:
+/// the fields, including skipped (`_`) ones, must cover exactly `MAX_BITS` bits.
+/// This catches fields that were forgotten or are too small.
+///
+/// ```
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     #[exhaustive]
+///     struct Complete<u8> {
+///         low: 4,
+///         _: 2,
+///         high: 2
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     // error: bitfield `Incomplete` is #[exhaustive], but its fields cover 6 of the 8 bits of `u8`:
+///     // 2 bits are missing, starting at bit 4 (use `_` to skip bits explicitly)
+///     #[exhaustive]
+///     struct Incomplete<u8> {
+///         low: 4,
+///         high @ 6: 2
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ## Visibility
+/// Fields accept the same visibility qualifiers as struct fields (`pub`, `pub(crate)`, `pub(super)`, `pub(in path)`).
+/// Fields without a qualifier are private: they can only be used in the module that invokes `bitfield!`.
+/// The visibility applies both to the field's type and to the member of `Fields` that gives access to it.
+///
+/// ```compile_fail
+/// mod registers {
+///     use simple_bitfield::bitfield;
+///
+///     bitfield!{
+///         pub struct Status<u8> {
+///             pub ready: 1,
+///             locked: 1 // Private
+///         }
+///     }
+/// }
+///
+/// # fn main() {
+/// use simple_bitfield::Field;
+///
+/// let status = registers::Status::new(0b11);
+/// assert_eq!(status.ready.get(), 1);
+/// assert_eq!(status.locked.get(), 1); // Error: `locked` is private
+/// # }
+/// ```
+///
+/// ## Field attributes
+/// Fields can be documented with doc comments, and can have a default value, given with `#[default(value)]`.
+/// Default values are used by [from_fields] for the fields that aren't listed and are zero unless given.
+/// Skipped fields (`_`) can only be documented.
+///
+/// ```
+/// use simple_bitfield::{ bitfield, Bitfield, Field };
+///
+/// bitfield!{
+///     struct Uart<u8> {
+///         /// Number of data bits minus 5
+///         #[default(3)]
+///         data_bits: 2,
+///         /// Reserved
+///         _: 1,
+///         stop_bits: 1
+///     }
+/// }
+///
+/// # fn main() {
+/// assert_eq!(Uart::data_bits::DEFAULT, 3);
+/// assert_eq!(Uart::Uart::DEFAULTS, 0b0_0_11);
+/// # }
+/// ```
+///
+/// ## Field groups
+/// Fields that only make sense together can be grouped with the `#[group(name: field, other_field, ...)]` attribute.
+/// A group is accessed like a field, its value is a tuple with the values of its fields, and `set` writes all of them at once
+/// (see [FieldGroup]). The group's name can have a visibility qualifier, just like a field's.
+/// A group can have at most 12 fields. The fields must exist and can't be listed twice, which is checked at compile-time:
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     // error: group `trim` of bitfield `Oscillator` refers to `medium`, which isn't a field of it
+///     #[group(trim: coarse, medium, fine)]
+///     struct Oscillator<u8> {
+///         coarse: 4,
+///         fine: 4
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     // error: group `all` of bitfield `Channels` has 13 fields, but groups can have at most 12 fields
+///     #[group(all: c0, c1, c2, c3, c4, c5, c6, c7, c8, c9, c10, c11, c12)]
+///     struct Channels<u16> {
+///         c0: 1, c1: 1, c2: 1, c3: 1, c4: 1, c5: 1, c6: 1, c7: 1, c8: 1, c9: 1, c10: 1, c11: 1, c12: 1
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ## Constant evaluation
+/// Besides `new`, each bitfield has a `ZERO` constant, and each field `name` gets `const` methods of the bitfield
+/// (with the field's visibility): `get_name()`, `set_name(value)` and `with_name(value)`, like [Field::get], [Field::set]
+/// and [Bitfield::with]. So bitfields can be built and inspected in `const` and `static` items:
+///
+/// ```
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     struct Ctrl<u8> {
+///         mode: 2,
+///         div: 4
+///     }
+/// }
+///
+/// const CFG: Ctrl::Ctrl = Ctrl::Ctrl::ZERO.with_mode(2).with_div(5);
+/// const DIV: u8 = CFG.get_div();
+///
+/// static TABLE: [Ctrl::Ctrl; 2] = [CFG, CFG.with_mode(1)];
+///
+/// # fn main() {
+/// assert_eq!(u8::from(CFG), 0b0001_0110);
+/// assert_eq!(DIV, 5);
+/// assert_eq!(u8::from(TABLE[1]), 0b0001_0101);
+/// # }
+/// ```
+///
+/// The prefixes keep these methods apart from each other and from the methods of [Bitfield], `Clone`, `Ord` etc.,
+/// so fields can be named `clone`, `max` or `set`. The only exception is `by_name`, whose accessors would shadow
+/// [Bitfield::get_by_name] and [Bitfield::set_by_name]:
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     struct Ctrl<u8> {
+///         // error: a field can't be named `by_name`
+///         by_name: 2
+///     }
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ## Modifying several fields at once
+/// Each call to `set` reads and writes the whole underlying value. `modify` sets any number of fields
+/// with a single read-modify-write instead: the closure gets a writer with a method per field
+/// (with the field's visibility), and the combined value is applied at once.
+/// The writer's type is `BitfieldName::Writer`.
+///
+/// ```
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     struct Control<u16> {
+///         enable: 1,
+///         mode: 3,
+///         _: 4,
+///         divider: 8
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut control = Control::new(0b0000_0001_0000_0000);
+/// control.modify(|w| w.mode(2).divider(5).enable(1));
+///
+/// assert_eq!(u16::from(control), 0b0000_0101_0000_0101);
+///
+/// fn reset(w: Control::Writer) -> Control::Writer {
+///     w.mode(0).divider(0)
+/// }
+///
+/// control.modify(reset);
+/// assert_eq!(u16::from(control), 0b0000_0000_0000_0001);
+/// # }
+/// ```
+///
+/// ## Builders
+/// Bitfields marked `#[builder]` get a `builder()` function that returns a builder with a setter per field
+/// (with the field's visibility). The builder's type keeps track of which fields have been set:
+/// each field can only be set once, and `build()` only compiles once all fields without a default value have been set.
+///
+/// ```
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     #[builder]
+///     struct Command<u16> {
+///         opcode: 4,
+///         #[default(1)]
+///         count: 4,
+///         address: 8
+///     }
+/// }
+///
+/// # fn main() {
+/// let command = Command::builder().opcode(0x3).address(0x42).build();
+///
+/// assert_eq!(u16::from(command), 0x42_1_3);
+/// # }
+/// ```
+///
+/// Forgetting a field is a type error: `build()` only exists for builders whose required fields are [Set],
+/// so it's reported by `cargo check` too, even in code that's never instantiated:
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     #[builder]
+///     struct Command<u16> {
+///         opcode: 4,
+///         #[default(1)]
+///         count: 4,
+///         address: 8
+///     }
+/// }
+///
+/// fn read<T>() -> Command::Command {
+///     // error[E0599]: no method named `build` found for struct `Command::Builder<Set>`
+///     // note: the method was found for `Command::Builder<Set, count, Set>`
+///     Command::builder().opcode(0x3).build()
+/// }
+/// # fn main() {}
+/// ```
+///
+/// ## Typed fields
+/// The fields of bitfields marked `#[typed]` return a [FieldValue] from `get`, and `set` only accepts
+/// a `FieldValue` of the same field, so the value of one field can't be written into another by mistake.
+/// The same goes for the bitfield's `get_field()`, `set_field(value)` and `with_field(value)`, the methods of its `Writer`,
+/// and the setters of its builder.
+/// Raw values are converted explicitly (see [FieldValue]) and can still be set with `set_checked`,
+/// and read with `Field::get(&my_bitfield.field)` or [Bitfield::get].
+///
+/// ```
+/// use simple_bitfield::{ bitfield, Field, FieldValue };
+///
+/// bitfield!{
+///     #[typed]
+///     struct Clock<u8> {
+///         source: 2,
+///         divider: 6
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut clock = Clock::new(0);
+/// clock.source.set(FieldValue::new(1).unwrap());
+/// clock.divider.set(FieldValue::MAX);
+///
+/// let source: FieldValue<Clock::source> = clock.source.get();
+/// assert_eq!(source.value(), 1);
+/// assert_eq!(Field::get(&clock.divider), 0b11_1111);
+///
+/// clock.set_source(source);
+/// clock.modify(|w| w.divider(FieldValue::new(3).unwrap()));
+/// assert_eq!(clock.get_divider().value(), 3);
+/// # }
+/// ```
+///
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// bitfield!{
+///     #[typed]
+///     struct Clock<u8> {
+///         source: 2,
+///         divider: 6
+///     }
+/// }
+///
+/// # fn main() {
+/// let mut clock = Clock::new(0);
+/// clock.set_source(clock.get_divider()); // error: expected `FieldValue<source>`, found `FieldValue<divider>`
+/// # }
+/// ```
+///
+/// ## Operators and comparisons
+/// Bitfields support the bitwise operators (`&`, `|`, `^`, `!` and the assigning `&=`, `|=`, `^=`)
+/// with bitfields of the same type and with raw values of the base type.
+/// They also implement `PartialEq`, `Eq`, `Hash`, `PartialOrd` and `Ord`, which compare the raw values,
+/// so they can be compared directly and used as keys of maps. Bitfields marked `#[no_compare]` don't implement
+/// the comparison traits, so they can implement them differently (for example, ignoring reserved bits).
+///
+/// ```
+/// use simple_bitfield::{ bitfield, mask_of };
+///
+/// bitfield!{
+///     struct Flags<u8> {
+///         read: 1,
+///         write: 1,
+///         execute: 1
+///     }
+/// }
+///
+/// # fn main() {
+/// let read_write = Flags::new(0b011);
+/// let write_execute = Flags::new(0b110);
+///
+/// assert_eq!(read_write & write_execute, Flags::new(0b010));
+/// assert_eq!(read_write | mask_of!(Flags: execute), Flags::new(0b111));
+/// assert!(read_write < write_execute);
+/// # }
+/// ```
 #[macro_export]
 macro_rules! bitfield {
-    ($($(#[$attr:meta])* $visibility:vis struct $bitfield_name:ident < $big_type:ty > { $($field:tt : $size:literal),* })*) => {$(
+    ($($(#[$($attr:tt)*])* $visibility:vis struct $bitfield_name:ident < $big_type:ty > { $($fields:tt)* })*) => {$(
+        $crate::bitfield!{
+            impl attrs [] { exhaustive: false, builder: false, typed: false, compare: true, groups: [] } [$(#[$($attr)*])*]
+            $visibility struct $bitfield_name < $big_type > { $($fields)* }
+        }
+    )*};
+
+    /* Attributes that configure the bitfield (like `#[exhaustive]`) are removed from the struct's attributes.
+     * This takes one step per attribute (or per 8 lines of documentation), so it doesn't hit the recursion limit.
+     */
+    (impl attrs [$($attrs:tt)*] { exhaustive: $exhaustive:tt, builder: $builder:tt, typed: $typed:tt, compare: $compare:tt, groups: $groups:tt } [#[exhaustive] $($rest:tt)*] $($bitfield:tt)*) => {
+        $crate::bitfield!{ impl attrs [$($attrs)*] { exhaustive: true, builder: $builder, typed: $typed, compare: $compare, groups: $groups } [$($rest)*] $($bitfield)* }
+    };
+    (impl attrs [$($attrs:tt)*] { exhaustive: $exhaustive:tt, builder: $builder:tt, typed: $typed:tt, compare: $compare:tt, groups: $groups:tt } [#[builder] $($rest:tt)*] $($bitfield:tt)*) => {
+        $crate::bitfield!{ impl attrs [$($attrs)*] { exhaustive: $exhaustive, builder: true, typed: $typed, compare: $compare, groups: $groups } [$($rest)*] $($bitfield)* }
+    };
+    (impl attrs [$($attrs:tt)*] { exhaustive: $exhaustive:tt, builder: $builder:tt, typed: $typed:tt, compare: $compare:tt, groups: $groups:tt } [#[typed] $($rest:tt)*] $($bitfield:tt)*) => {
+        $crate::bitfield!{ impl attrs [$($attrs)*] { exhaustive: $exhaustive, builder: $builder, typed: true, compare: $compare, groups: $groups } [$($rest)*] $($bitfield)* }
+    };
+    (impl attrs [$($attrs:tt)*] { exhaustive: $exhaustive:tt, builder: $builder:tt, typed: $typed:tt, compare: $compare:tt, groups: $groups:tt } [#[no_compare] $($rest:tt)*] $($bitfield:tt)*) => {
+        $crate::bitfield!{ impl attrs [$($attrs)*] { exhaustive: $exhaustive, builder: $builder, typed: $typed, compare: false, groups: $groups } [$($rest)*] $($bitfield)* }
+    };
+    (impl attrs [$($attrs:tt)*] { exhaustive: $exhaustive:tt, builder: $builder:tt, typed: $typed:tt, compare: $compare:tt, groups: [$($groups:tt)*] } [#[group $group:tt] $($rest:tt)*] $($bitfield:tt)*) => {
+        $crate::bitfield!{ impl attrs [$($attrs)*] { exhaustive: $exhaustive, builder: $builder, typed: $typed, compare: $compare, groups: [$($groups)* $group] } [$($rest)*] $($bitfield)* }
+    };
+    (impl attrs [$($attrs:tt)*] { $($options:tt)* } [
+        #[doc = $doc_0:literal] #[doc = $doc_1:literal] #[doc = $doc_2:literal] #[doc = $doc_3:literal]
+        #[doc = $doc_4:literal] #[doc = $doc_5:literal] #[doc = $doc_6:literal] #[doc = $doc_7:literal]
+        $($rest:tt)*
+    ] $($bitfield:tt)*) => {
+        $crate::bitfield!{
+            impl attrs [
+                $($attrs)*
+                #[doc = $doc_0] #[doc = $doc_1] #[doc = $doc_2] #[doc = $doc_3]
+                #[doc = $doc_4] #[doc = $doc_5] #[doc = $doc_6] #[doc = $doc_7]
+            ] { $($options)* } [$($rest)*] $($bitfield)*
+        }
+    };
+    (impl attrs [$($attrs:tt)*] { $($options:tt)* } [#[$($attr:tt)*] $($rest:tt)*] $($bitfield:tt)*) => {
+        $crate::bitfield!{ impl attrs [$($attrs)* #[$($attr)*]] { $($options)* } [$($rest)*] $($bitfield)* }
+    };
+    (impl attrs [$($attrs:tt)*] { $($options:tt)* } [] $($bitfield:tt)*) => {
+        $crate::bitfield!{ impl struct [$($attrs)*] { $($options)* } $($bitfield)* }
+    };
+
+    (impl struct [$($attr:tt)*] {
+        exhaustive: $exhaustive:tt,
+        builder: $builder:tt,
+        typed: $typed:tt,
+        compare: $compare:tt,
+        // Each group is `name: fields`, where `name` can have a visibility qualifier like a field's
+        groups: [$(($group_start:ident $(($($group_restriction:tt)*))? $($group_name:ident)? : $($member:ident),+ $(,)?))*]
+    } $visibility:vis struct $bitfield_name:ident < $big_type:ty > {
+        /* Each field is either `_` or `name`, `pub name`, `pub(...) name`.
+         * `$field_start` is the field's name or `pub`, `$field_name` is the name that follows `pub`.
+         * All fields are parsed at once (without recursion), so bitfields can have hundreds of fields.
+         */
+        $($(#[$($field_attr:tt)*])* $($field_start:ident $(($($restriction:tt)*))? $($field_name:ident)?)? $(_)? $(@ $offset:tt)? : $size:expr),* $(,)?
+    }) => {
         // Construct the whole module
         #[allow(non_snake_case)]
         #[allow(dead_code)]
@@ -343,10 +1369,70 @@ macro_rules! bitfield {
             /// Struct with the actual data.
             #[repr(transparent)]
             #[derive(Copy, Clone)]
-            $(#[$attr])*
+            $($attr)*
             pub struct $bitfield_name($big_type);
             impl $crate::Bitfield for $bitfield_name {
                 type BaseType = $big_type;
+
+                const NAME: &'static str = stringify!($bitfield_name);
+
+                const FIELD_COUNT: usize = $crate::named_field_count(__FIELD_SPEC);
+
+                const USED_MASK: $big_type = {
+                    let mut mask = 0;
+
+                    let mut i = 0;
+                    while i < Self::LAYOUT.len() {
+                        if !Self::LAYOUT[i].reserved {
+                            mask |= Self::LAYOUT[i].mask << Self::LAYOUT[i].offset;
+                        }
+                        i += 1;
+                    }
+
+                    mask
+                };
+
+                const UNUSED_MASK: $big_type = !Self::USED_MASK;
+
+                const DEFAULTS: $big_type = 0 $(| $crate::bitfield!(impl default_bits $bitfield_name [$($field_start $($field_name)?)?]))*;
+
+                fn from_raw(raw: $big_type) -> Self {
+                    Self(raw)
+                }
+
+                fn into_raw(self) -> $big_type {
+                    self.0
+                }
+
+                fn raw_ref(&self) -> &$big_type {
+                    &self.0
+                }
+
+                fn raw_mut(&mut self) -> &mut $big_type {
+                    &mut self.0
+                }
+
+                const LAYOUT: &'static [$crate::FieldInfo<$big_type>] = &{
+                    let placeholder = $crate::FieldInfo {
+                        name: "", offset: 0, size: 0, mask: 0, access: $crate::Access::None, reserved: true
+                    };
+                    let mut layout = [placeholder; __FIELD_SPEC.len()];
+
+                    let mut i = 0;
+                    while i < layout.len() {
+                        let size = __FIELD_SPEC[i].size;
+                        let mask: $big_type = if size >= Self::MAX_BITS as u32 { !0 } else { !(!0 << size) };
+
+                        layout[i] = $crate::field_info(__FIELD_SPEC, &__FIELD_OFFSETS, i, mask);
+                        i += 1;
+                    }
+
+                    layout
+                };
+
+                fn visit_fields<V: $crate::FieldVisitor<Self>>(&self, visitor: &mut V) {
+                    $($crate::bitfield!(impl visit_entry self visitor [$($field_start $($field_name)?)?]);)*
+                }
             }
 
             impl From<$big_type> for $bitfield_name
@@ -362,111 +1448,290 @@ macro_rules! bitfield {
                 }
             }
 
+            // Bitwise operators between bitfields, and with raw masks
+            $crate::bitfield!{ impl bit_op $bitfield_name [$big_type] BitAnd bitand BitAndAssign bitand_assign & }
+            $crate::bitfield!{ impl bit_op $bitfield_name [$big_type] BitOr bitor BitOrAssign bitor_assign | }
+            $crate::bitfield!{ impl bit_op $bitfield_name [$big_type] BitXor bitxor BitXorAssign bitxor_assign ^ }
+
+            impl core::ops::Not for $bitfield_name {
+                type Output = Self;
+
+                fn not(self) -> Self {
+                    Self(!self.0)
+                }
+            }
+
+            // Comparisons of the raw values, unless `#[no_compare]`
+            $crate::bitfield!{ impl compare $compare $bitfield_name }
+
             /// Creates a new bitfield
             pub const fn new(val: $big_type) -> $bitfield_name {
                 // Can't use `val.into()` because `into` is not `const`.
                 $bitfield_name(val)
             }
 
-            /* Generate a zero-sized (!!) `struct` for each `$field`
-             * and a zero-sized (!!) `struct Field` whose elements are objects of these structs.
-             */
+            #[allow(dead_code)]
+            impl $bitfield_name {
+                /// The bitfield whose bits are all zero.
+                pub const ZERO: Self = Self(0);
+            }
+
+            // Generate the fields' typed `get` and `set`, if `#[typed]`
+            $(
+                $crate::bitfield!{ impl typed_field $typed $bitfield_name [$($field_start $($field_name)?)?] }
+            )*
+
+            // Generate the builder, if `#[builder]`
             $crate::bitfield!{
-                impl
-                $($field : $size),* end_marker // List of fields to process
-    
-                Fields, // Name of the struct that will hold the resulting fields
-                $bitfield_name, // Name of the underlying bitfield struct that holds the actual data
-                0, // Offset of the current bitfield
-                processed // Empty (!) list of processed field names
+                impl builder $builder $typed $bitfield_name [$($(($($field_name)? $field_start [$field_start $(($($restriction)*))? $($field_name)?]))?)*]
+                [$((($($($field_name)? $field_start)?) [$(#[$($field_attr)*])*]))*]
             }
 
-            $crate::const_assert!(Fields::VALID);
+            /// Sizes and explicit offsets of all fields (including `_`), used to compute the fields' offsets.
+            /// Evaluated outside of this module, see below.
+            const __FIELD_SPEC: &[$crate::FieldSpec] = $bitfield_name::__FIELD_SPEC;
+
+            /// Offsets of all entries of `__FIELD_SPEC`, computed once for the whole bitfield.
+            const __FIELD_OFFSETS: [u32; __FIELD_SPEC.len()] = $crate::field_offsets(__FIELD_SPEC);
+
+            /// Index in `__FIELD_SPEC` of each named field, by its position in `__FieldIndex`.
+            const __FIELD_INDICES: [usize; <$bitfield_name as $crate::Bitfield>::FIELD_COUNT] = $crate::named_field_indices(__FIELD_SPEC);
+
+            // Each named field's position among the named fields
+            $crate::bitfield!{ impl field_indices [$($(($($field_name)? $field_start))?)*] }
+
+            // Check that the fields fit into `$big_type` and don't overlap (and cover all of it, if `#[exhaustive]`)
+            const _: () = $crate::check_layout(
+                __FIELD_SPEC, &__FIELD_OFFSETS, <$bitfield_name as $crate::Bitfield>::MAX_BITS, $exhaustive,
+                stringify!($bitfield_name), stringify!($big_type)
+            );
+
+            // Generate a zero-sized (!!) `struct` for each field
+            $(
+                $crate::bitfield!{
+                    impl module_item field_item [$($field_start $(($($restriction)*))? $($field_name)?)?] [$(#[$($field_attr)*])*] $typed $bitfield_name
+                }
+            )*
+
+            // Generate a zero-sized `struct` for each group of fields
+            $(
+                $crate::bitfield!{
+                    impl module_item group_item [$group_start $(($($group_restriction)*))? $($group_name)?] [$($member),+] $bitfield_name
+                }
+            )*
+
+            /// Struct whose fields' names' are those of the bitfield's fields.
+            ///
+            /// When accessing a field of a bitfield like `some_bitfield.a_field`, a reference to `some_bitfield` is created
+            /// and `unsafe`ly treated as a reference to _this struct_.
+            /// However, this should actually be OK because this struct can't be constructed since none of its fields can be constructed.
+            ///
+            /// This struct's size is zero:
+            /// ```
+            /// use simple_bitfield::bitfield;
+            ///
+            /// bitfield!{
+            ///     struct BitfieldName<u8> {
+            ///         first_two_bits: 2,
+            ///         three_more_bits: 3
+            ///     }
+            /// }
+            ///
+            /// # fn main() {
+            /// assert_eq!(core::mem::size_of::<BitfieldName::Fields>(), 0);
+            /// # }
+            /// ```
+            pub type Fields = <$bitfield_name as core::ops::Deref>::Target;
+
+            /// Collects the new values of several fields, see `modify`.
+            ///
+            /// Has a method per field, with the field's visibility, that sets the field's new value.
+            pub struct Writer {
+                // The methods are defined in the module that invokes `bitfield!`, so that they have the fields' visibility
+                pub(super) mask: $big_type,
+                pub(super) value: $big_type
+            }
+
+            // Lets `mask_of!` name the bitfield's type given the module's path
+            #[doc(hidden)]
+            pub type __Bitfield = $bitfield_name;
+
+            impl core::fmt::Display for $bitfield_name {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::result::Result<(), core::fmt::Error> {
+                    write!(f, "{}({})", stringify!($bitfield_name), self.0)
+                }
+            }
+        }
+
+        /* The struct that holds the fields is defined in the module that invokes `bitfield!`
+         * (but can't be named there), so that the fields' visibility means what the user wrote.
+         */
+        const _: () = {
+            /* Field sizes, offsets and default values are evaluated here rather than in the bitfield's module,
+             * so that they can refer to anything in scope where `bitfield!` is invoked (and nothing else).
+             */
+            impl $bitfield_name::$bitfield_name {
+                const __FIELD_SPEC: &'static [$crate::FieldSpec] = &[$(
+                    $crate::bitfield!(impl spec $bitfield_name [$($field_start $($field_name)?)?] [$($offset)?] [$size])
+                ),*];
+            }
+
+            $(
+                $crate::bitfield!{ impl field_default [$($field_start $($field_name)?)?] $bitfield_name [$(#[$($field_attr)*])*] }
+            )*
+
+            #[repr(C)]
+            #[allow(dead_code)]
+            pub struct Fields {
+                $($($field_start $(($($restriction)*))? $($field_name)? : $crate::bitfield!(impl field_type $bitfield_name [$field_start $($field_name)?]),)?)*
+                $($group_start $(($($group_restriction)*))? $($group_name)? : $crate::bitfield!(impl field_type $bitfield_name [$group_start $($group_name)?]),)*
+            }
 
             /// Implement this so that accesses to fields of `$bitfield_name`
             /// actually access the zero-sized struct `Fields`
-            impl core::ops::Deref for $bitfield_name {
+            impl core::ops::Deref for $bitfield_name::$bitfield_name {
                 type Target = Fields;
 
                 fn deref(&self) -> &Self::Target {
                     // We go through Deref here because Fields MUST NOT be moveable.
-                    unsafe { &*(self as *const Self as *const Fields) } 
+                    unsafe { &*(self as *const Self as *const Fields) }
                 }
             }
 
-            impl core::ops::DerefMut for $bitfield_name {
+            impl core::ops::DerefMut for $bitfield_name::$bitfield_name {
                 fn deref_mut(&mut self) -> &mut Self::Target {
                     // We go through Deref here because Fields MUST NOT be moveable.
-                    unsafe { &mut *(self as *mut Self as *mut Fields) } 
+                    unsafe { &mut *(self as *mut Self as *mut Fields) }
                 }
             }
-        }
-    )*};
 
-    (impl end_marker $struct_name:ident, $bitfield_type:ty, $curr_offset:expr, processed $(| $field_processed:ident)*) => {
-        /// Struct whose fields' names' are those of the bitfield's fields.
-        ///
-        /// When accessing a field of a bitfield like `some_bitfield.a_field`, a reference to `some_bitfield` is created
-        /// and `unsafe`ly treated as a reference to _this struct_.
-        /// However, this should actually be OK because this struct can't be constructed since none of its fields can be constructed.
-        ///
-        /// This struct's size is zero:
-        /// ```
-        /// use simple_bitfield::bitfield;
-        ///
-        /// bitfield!{
-        ///     struct BitfieldName<u8> {
-        ///         first_two_bits: 2,
-        ///         three_more_bits: 3
-        ///     }
-        /// }
-        ///
-        /// # fn main() {
-        /// assert_eq!(core::mem::size_of::<BitfieldName::Fields>(), 0);
-        /// # }
-        /// ```
-        #[repr(C)]
-        pub struct $struct_name {
-            $(pub $field_processed: $field_processed),*
-        }
+            #[allow(dead_code)]
+            impl $bitfield_name::Writer {
+                $($crate::bitfield!{ impl write_method [$($field_start $(($($restriction)*))? $($field_name)?)?] $typed $bitfield_name })*
+            }
+
+            #[allow(dead_code)]
+            impl $bitfield_name::$bitfield_name {
+                /// Sets the fields chosen by `write` with a single read-modify-write of the underlying value.
+                pub fn modify(&mut self, write: impl core::ops::FnOnce($bitfield_name::Writer) -> $bitfield_name::Writer) {
+                    let $bitfield_name::Writer { mask, value } = write($bitfield_name::Writer { mask: 0, value: 0 });
+                    let raw = <Self as $crate::Bitfield>::raw_mut(self);
+
+                    *raw = (*raw & !mask) | value;
+                }
+            }
+
+            impl core::fmt::Debug for $bitfield_name::$bitfield_name {
+                fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::result::Result<(), core::fmt::Error> {
+                    // `None` for skipped fields
+                    let fields: &[core::option::Option<(&str, $big_type)>] = &[
+                        $($crate::bitfield!(impl debug_entry self [$($field_start $($field_name)?)?])),*
+                    ];
+
+                    write!(f, "{}(", stringify!($bitfield_name))?;
+                    for (i, (name, value)) in fields.iter().flatten().enumerate() {
+                        if i > 0 {
+                            write!(f, ", ")?;
+                        }
+                        write!(f, "{}: {:?}", name, value)?;
+                    }
+                    write!(f, ")")
+                }
+            }
+        };
+    };
+
+    // Skipped fields (`_`) don't generate any items, and can only have documentation
+    (impl module_item field_item [] [$($attr:tt)*] $typed:tt $bitfield_type:ty) => {
+        $crate::bitfield!{ impl skipped_attrs $($attr)* }
+    };
+    (impl module_item $item:ident [] $($rest:tt)*) => {};
+
+    (impl skipped_attrs #[doc $($doc:tt)*] $($rest:tt)*) => { $crate::bitfield!{ impl skipped_attrs $($rest)* } };
+    (impl skipped_attrs #[$($attr:tt)*] $($rest:tt)*) => {
+        compile_error!(concat!("skipped fields (`_`) can't have the attribute `#[", stringify!($($attr)*), "]`"));
+    };
+    (impl skipped_attrs) => {};
+
+    /* Fields (and groups) are generated inside the bitfield's module, so the visibility the user wrote
+     * (which is relative to the module that invokes `bitfield!`) must be moved one module up.
+     * `$item` is the rule that generates the item.
+     */
+    (impl module_item $item:ident [$name:ident] $($rest:tt)*) => {
+        // Private fields are visible in the module that invokes `bitfield!`, just like private struct fields
+        $crate::bitfield!{ impl $item [pub(super)] $name $($rest)* }
+    };
 
-        impl $struct_name {
-            /// `true` if ALL fields are valid, `false` otherwise
-            const VALID: bool = $(<$field_processed as $crate::Field<$bitfield_type>>::VALID &)* true;
-        }
+    (impl module_item $item:ident [pub $name:ident] $($rest:tt)*) => {
+        $crate::bitfield!{ impl $item [pub] $name $($rest)* }
+    };
 
-        impl core::fmt::Display for $bitfield_type {
-            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::result::Result<(), core::fmt::Error> {
-                write!(f, "{}({})", stringify!($bitfield_type), self.0)
-            }
-        }
+    (impl module_item $item:ident [pub (crate) $name:ident] $($rest:tt)*) => {
+        $crate::bitfield!{ impl $item [pub(crate)] $name $($rest)* }
+    };
 
-        impl core::fmt::Debug for $bitfield_type {
-            fn fmt(&self, f: &mut core::fmt::Formatter<'_>) -> core::result::Result<(), core::fmt::Error> {
-                use $crate::Field; // because `gen_format` accesses fields
+    (impl module_item $item:ident [pub (self) $name:ident] $($rest:tt)*) => {
+        $crate::bitfield!{ impl $item [pub(super)] $name $($rest)* }
+    };
 
-                write!(f, "{}", format_args!(
-                    "{}({})", stringify!($bitfield_type),
-                    $crate::gen_format_debug!(self $(| $field_processed)*)
-                ))
-            }
-        }
+    (impl module_item $item:ident [pub (super) $name:ident] $($rest:tt)*) => {
+        $crate::bitfield!{ impl $item [pub(in super::super)] $name $($rest)* }
     };
 
-    (impl _ : $size:literal $(, $other_field:tt : $other_size:literal)* end_marker $struct_name:ident, $bitfield_type:ty, $curr_offset:expr, processed $(| $field_processed:ident)*) => {
-        // Skip field that's equal to `_`
-        $crate::bitfield!{
-            impl
-            $($other_field : $other_size),* end_marker
-            $struct_name, $bitfield_type,
-            $curr_offset + $size,
-            processed $(| $field_processed)*
-        }
+    (impl module_item $item:ident [pub (in crate $($path:tt)*) $name:ident] $($rest:tt)*) => {
+        $crate::bitfield!{ impl $item [pub(in crate $($path)*)] $name $($rest)* }
+    };
+
+    (impl module_item $item:ident [pub (in self $($path:tt)*) $name:ident] $($rest:tt)*) => {
+        $crate::bitfield!{ impl $item [pub(in super $($path)*)] $name $($rest)* }
+    };
+
+    (impl module_item $item:ident [pub (in super $($path:tt)*) $name:ident] $($rest:tt)*) => {
+        $crate::bitfield!{ impl $item [pub(in super::super $($path)*)] $name $($rest)* }
+    };
+
+    (impl module_item $item:ident [$($name:tt)*] $($rest:tt)*) => {
+        compile_error!(concat!("invalid visibility qualifier: `", stringify!($($name)*), "`"));
+    };
+
+    /* A field's attributes are its documentation and its default value (`#[default(value)]`).
+     * They're collected one by one: `[default] [documentation] [attributes left]`.
+     */
+    // The field's accessors `get_by_name` and `set_by_name` would shadow `Bitfield::get_by_name` and `Bitfield::set_by_name`
+    (impl field_item $visibility:tt by_name $($rest:tt)*) => {
+        compile_error!("a field can't be named `by_name`, because its accessors would shadow `Bitfield::get_by_name` and `Bitfield::set_by_name`");
+    };
+    (impl field_item [$($visibility:tt)*] $field:ident [$($attr:tt)*] $typed:tt $bitfield_type:ty) => {
+        $crate::bitfield!{ impl field_attrs [$($visibility)*] $field $typed $bitfield_type [] [] [$($attr)*] }
+    };
+
+    (impl field_attrs $visibility:tt $field:ident $typed:tt $bitfield_type:tt [$($default:tt)*] [$($doc:tt)*] [
+        #[doc $($this_doc:tt)*] $($rest:tt)*
+    ]) => {
+        $crate::bitfield!{ impl field_attrs $visibility $field $typed $bitfield_type [$($default)*] [$($doc)* #[doc $($this_doc)*]] [$($rest)*] }
+    };
+
+    (impl field_attrs $visibility:tt $field:ident $typed:tt $bitfield_type:tt [] $doc:tt [
+        #[default($value:expr)] $($rest:tt)*
+    ]) => {
+        $crate::bitfield!{ impl field_attrs $visibility $field $typed $bitfield_type [($value)] $doc [$($rest)*] }
     };
 
-    (impl $field:ident : $size:literal $(, $other_field:tt : $other_size:literal)* end_marker $struct_name:ident, $bitfield_type:ty, $curr_offset:expr, processed $(| $field_processed:ident)*) => {
-        // Create one field
+    (impl field_attrs $visibility:tt $field:ident $typed:tt $bitfield_type:tt [$default:tt] $doc:tt [
+        #[default $($value:tt)*] $($rest:tt)*
+    ]) => {
+        compile_error!(concat!("field `", stringify!($field), "` has more than one default value"));
+    };
+
+    (impl field_attrs $visibility:tt $field:ident $typed:tt $bitfield_type:tt $default:tt $doc:tt [
+        #[$($attr:tt)*] $($rest:tt)*
+    ]) => {
+        compile_error!(concat!("field `", stringify!($field), "` has an unsupported attribute: `#[", stringify!($($attr)*), "]`"));
+    };
 
+    (impl field_attrs [$($visibility:tt)*] $field:ident $typed:tt $bitfield_type:ty [$($default:tt)?] [$($doc:tt)*] []) => {
+        $($doc)*
+        ///
         /// The bitfield's field. Can't be constructed outside of a bitfield.
         ///
         /// It's actually a struct of size ZERO and implements `Field<UnderlyingBitfieldType>`, so that its value can be obtained with `get()` and changed with `set()`.
@@ -487,7 +1752,7 @@ macro_rules! bitfield {
         /// # }
         /// ```
         #[allow(non_camel_case_types)]
-        pub struct $field(());
+        $($visibility)* struct $field(());
         /*
          * `struct thing(())` is a "unit-valued tuple struct",
          * basically the same as `struct thing(<any type>)`,
@@ -499,43 +1764,717 @@ macro_rules! bitfield {
 
         #[allow(dead_code)]
         impl $crate::Field<$bitfield_type> for $field {
-            const SIZE: u8 = $size;
-            const OFFSET: u8 = $curr_offset;
-            const MASK: <$bitfield_type as $crate::Bitfield>::BaseType = (1 << Self::SIZE) - 1;
+            const SIZE: u8 = __FIELD_SPEC[__FIELD_INDICES[__FieldIndex::$field as usize]].size as u8;
+            const OFFSET: u8 = __FIELD_OFFSETS[__FIELD_INDICES[__FieldIndex::$field as usize]] as u8;
+            const MASK: <$bitfield_type as $crate::Bitfield>::BaseType =
+                if Self::SIZE >= <$bitfield_type as $crate::Bitfield>::MAX_BITS { !0 } else { !(!0 << Self::SIZE) };
+            const POSITIONED_MASK: <$bitfield_type as $crate::Bitfield>::BaseType = Self::MASK << Self::OFFSET;
+            const DEFAULT: <$bitfield_type as $crate::Bitfield>::BaseType = $crate::bitfield!(impl default_value $($default)?);
 
             #[inline]
             fn is_set(&self) -> bool {
-                self.get() != 0
+                <Self as $crate::Field<$bitfield_type>>::get(self) != 0
             }
         }
 
-        $crate::const_assert!(<$field as $crate::Field<$bitfield_type>>::VALID);
+        impl $crate::TypedField for $field {
+            type Bitfield = $bitfield_type;
+        }
 
-        // Process the next fields
-        $crate::bitfield!{
-            impl
-            $($other_field : $other_size),* end_marker // Schedule the next fields
-            $struct_name, $bitfield_type, // Pass along
-            $curr_offset + $size, // INCREMENT the current offset!!
-            processed $(| $field_processed)* | $field // Add the field name to processed fields
-            /* The trick with field names being separated by pipes (`|`) like `| $field`
-             * is needed because `$(| $field_processed)*` may be empty, but we apparently need SOME separator,
-             * so the separator must be in front of the field name
-             */
+        // `const` accessors named after the field, with the field's visibility
+        $crate::__paste! {
+            #[allow(dead_code)]
+            impl $bitfield_type {
+                #[doc = concat!("Returns the value of the field `", stringify!($field), "`. Like `Field::get`, but `const`.")]
+                $($visibility)* const fn [<get_ $field>](&self) -> $crate::bitfield!(impl value_type $typed [$field] [$bitfield_type]) {
+                    let value = (self.0 >> <$field as $crate::Field<$bitfield_type>>::OFFSET) & <$field as $crate::Field<$bitfield_type>>::MASK;
+                    $crate::bitfield!(impl to_value $typed [$field] value)
+                }
+
+                #[doc = concat!("Sets the value of the field `", stringify!($field), "`. Like `Field::set`, but `const`.")]
+                $($visibility)* const fn [<set_ $field>](&mut self, new_value: $crate::bitfield!(impl value_type $typed [$field] [$bitfield_type])) {
+                    self.0 = (self.0 & !<$field as $crate::Field<$bitfield_type>>::POSITIONED_MASK)
+                        | (($crate::bitfield!(impl from_value $typed new_value) & <$field as $crate::Field<$bitfield_type>>::MASK)
+                            << <$field as $crate::Field<$bitfield_type>>::OFFSET);
+                }
+
+                #[doc = concat!("Returns a copy of the bitfield with the field `", stringify!($field), "` set to `new_value`. Like `Bitfield::with`, but `const`.")]
+                $($visibility)* const fn [<with_ $field>](mut self, new_value: $crate::bitfield!(impl value_type $typed [$field] [$bitfield_type])) -> Self {
+                    self.[<set_ $field>](new_value);
+                    self
+                }
+            }
         }
-    }
+
+        // Check that the default value fits into the field
+        #[allow(clippy::bad_bit_mask)]
+        const _: () = if <$field as $crate::Field<$bitfield_type>>::DEFAULT & !<$field as $crate::Field<$bitfield_type>>::MASK != 0 {
+            panic!(concat!("the default value of field `", stringify!($field), "` doesn't fit into it"))
+        };
+    };
+
+    (impl default_bits $bitfield_name:ident [pub $field:ident]) => { $crate::bitfield!(impl default_bits $bitfield_name [$field]) };
+    (impl default_bits $bitfield_name:ident [$field:ident]) => {
+        <$field as $crate::Field<$bitfield_name>>::DEFAULT << <$field as $crate::Field<$bitfield_name>>::OFFSET
+    };
+    (impl default_bits $bitfield_name:ident []) => { 0 };
+
+    // The default value itself is evaluated by `impl field_default`
+    (impl default_value) => { 0 };
+    (impl default_value ($value:expr)) => { Self::__DEFAULT };
+
+    /* Emitted where `bitfield!` is invoked, so the default value can refer to anything in scope there.
+     * The field's other attributes are checked by `impl field_attrs`, so they're just skipped here.
+     */
+    (impl field_default [pub $field:ident] $($rest:tt)*) => { $crate::bitfield!{ impl field_default [$field] $($rest)* } };
+    (impl field_default [] $($rest:tt)*) => {};
+    (impl field_default [$field:ident] $bitfield_name:ident [#[default($value:expr)] $($rest:tt)*]) => {
+        impl $bitfield_name::$field {
+            const __DEFAULT: <$bitfield_name::$bitfield_name as $crate::Bitfield>::BaseType = $value;
+        }
+    };
+    (impl field_default [$field:ident] $bitfield_name:ident [#[$($attr:tt)*] $($rest:tt)*]) => {
+        $crate::bitfield!{ impl field_default [$field] $bitfield_name [$($rest)*] }
+    };
+    (impl field_default [$field:ident] $bitfield_name:ident []) => {};
+
+    /* With `#[typed]`, the fields' inherent `get` and `set` take precedence over `Field::get` and `Field::set`,
+     * so `my_bitfield.field.get()` returns a `FieldValue` and the raw value needs `Field::get(&my_bitfield.field)`.
+     */
+    (impl typed_field false $($rest:tt)*) => {};
+    (impl typed_field true $bitfield_name:ident [pub $field:ident]) => { $crate::bitfield!{ impl typed_field true $bitfield_name [$field] } };
+    (impl typed_field true $bitfield_name:ident [$field:ident]) => {
+        impl $field {
+            /// Returns the current value of the field, which can only be written into this field.
+            pub fn get(&self) -> $crate::FieldValue<Self> {
+                $crate::FieldValue::masked(<Self as $crate::Field<$bitfield_name>>::get(self))
+            }
+
+            /// Sets the value of the field.
+            /// Raw values have to be converted explicitly, see `FieldValue`, or set with `set_checked`.
+            pub fn set(&mut self, new_value: $crate::FieldValue<Self>) {
+                <Self as $crate::Field<$bitfield_name>>::set(self, new_value.value())
+            }
+        }
+    };
+    (impl typed_field true $bitfield_name:ident []) => {};
+
+    /* The type of the values that a field's accessors, `Writer` method and builder setter take and return:
+     * `FieldValue<field>` with `#[typed]`, so that values of one field can't be written into another, and the raw value otherwise.
+     */
+    (impl value_type false [$field:ty] [$bitfield_type:ty]) => { <$bitfield_type as $crate::Bitfield>::BaseType };
+    (impl value_type true [$field:ty] [$bitfield_type:ty]) => { $crate::FieldValue<$field> };
+
+    // Converts a raw value that fits into the field to `impl value_type`
+    (impl to_value false [$field:ty] $value:expr) => { $value };
+    (impl to_value true [$field:ty] $value:expr) => { $crate::FieldValue::<$field>::__from_masked($value) };
+
+    // Converts a value of `impl value_type` to the raw value
+    (impl from_value false $value:expr) => { $value };
+    (impl from_value true $value:expr) => { $value.value() };
+
+    (impl bit_op $bitfield_name:ident [$big_type:ty] $op_trait:ident $method:ident $assign_trait:ident $assign_method:ident $op:tt) => {
+        impl core::ops::$op_trait for $bitfield_name {
+            type Output = Self;
+
+            fn $method(self, rhs: Self) -> Self {
+                Self(self.0 $op rhs.0)
+            }
+        }
+
+        impl core::ops::$op_trait<$big_type> for $bitfield_name {
+            type Output = Self;
+
+            fn $method(self, rhs: $big_type) -> Self {
+                Self(self.0 $op rhs)
+            }
+        }
+
+        impl core::ops::$assign_trait for $bitfield_name {
+            fn $assign_method(&mut self, rhs: Self) {
+                self.0 = self.0 $op rhs.0
+            }
+        }
+
+        impl core::ops::$assign_trait<$big_type> for $bitfield_name {
+            fn $assign_method(&mut self, rhs: $big_type) {
+                self.0 = self.0 $op rhs
+            }
+        }
+    };
+
+    (impl compare false $bitfield_name:ident) => {};
+    (impl compare true $bitfield_name:ident) => {
+        impl PartialEq for $bitfield_name {
+            fn eq(&self, other: &Self) -> bool {
+                self.0 == other.0
+            }
+        }
+
+        impl Eq for $bitfield_name {}
+
+        impl core::hash::Hash for $bitfield_name {
+            fn hash<H: core::hash::Hasher>(&self, hasher: &mut H) {
+                self.0.hash(hasher)
+            }
+        }
+
+        impl PartialOrd for $bitfield_name {
+            fn partial_cmp(&self, other: &Self) -> Option<core::cmp::Ordering> {
+                Some(self.cmp(other))
+            }
+        }
+
+        impl Ord for $bitfield_name {
+            fn cmp(&self, other: &Self) -> core::cmp::Ordering {
+                self.0.cmp(&other.0)
+            }
+        }
+    };
+
+
+    /* The builder has one type parameter per named field, named like the field, which is `Unset` or `Set`.
+     * Each named field is passed as `(name [visibility name])`, with `pub` (if any) after the name so that it's ignored.
+     * Each field gets its own setter, which needs the names of the fields before and after it.
+     * All fields (including `_`) are passed again with their attributes, as `((name) [attributes])`, see `impl build`.
+     */
+    (impl builder false $($rest:tt)*) => {};
+
+    (impl builder true $typed:tt $bitfield_name:ident [$(($field:ident $($start:ident)? [$($visibility:tt)*]))*] [$($field_attrs:tt)*]) => {
+        /// Builds the bitfield field by field, see `builder`.
+        ///
+        /// Each type parameter is the state of the field with the same name: `Unset` or `Set`.
+        #[allow(non_camel_case_types)]
+        pub struct Builder<$($field = $crate::Unset),*> {
+            raw: <$bitfield_name as $crate::Bitfield>::BaseType,
+            state: core::marker::PhantomData<fn() -> ($($field,)*)>
+        }
+
+        /// Creates a builder in which all fields are unset and have their default values.
+        pub const fn builder() -> Builder {
+            Builder { raw: <$bitfield_name as $crate::Bitfield>::DEFAULTS, state: core::marker::PhantomData }
+        }
+
+        $crate::bitfield!{ impl build $bitfield_name [] [] [$($field_attrs)*] }
+        $crate::bitfield!{ impl builder_setters $typed $bitfield_name [] [$(($field [$($visibility)*]))*] }
+    };
+
+    /* `build` is implemented for the builders whose fields without a default value are `Set`,
+     * so only the type parameters of the fields with a default value are left generic.
+     * Whether a field has a default is decided from its attributes: `[parameters] [[state]...] [fields left]`.
+     * Fields whose attributes are only documentation are required, several fields are handled per step
+     * so that bitfields with many fields don't hit the recursion limit.
+     */
+    (impl build $bitfield_name:ident [$($param:ident)*] [$($state:tt)*] [
+        (($field_0:ident $($start_0:ident)?) [$(#[doc $($doc_0:tt)*])*])
+        (($field_1:ident $($start_1:ident)?) [$(#[doc $($doc_1:tt)*])*])
+        (($field_2:ident $($start_2:ident)?) [$(#[doc $($doc_2:tt)*])*])
+        (($field_3:ident $($start_3:ident)?) [$(#[doc $($doc_3:tt)*])*])
+        $($rest:tt)*
+    ]) => {
+        $crate::bitfield!{ impl build $bitfield_name [$($param)*] [$($state)* [$crate::Set] [$crate::Set] [$crate::Set] [$crate::Set]] [$($rest)*] }
+    };
+    (impl build $bitfield_name:ident [$($param:ident)*] [$($state:tt)*] [
+        (($field_0:ident $($start_0:ident)?) [$(#[doc $($doc_0:tt)*])*])
+        (($field_1:ident $($start_1:ident)?) [$(#[doc $($doc_1:tt)*])*])
+        $($rest:tt)*
+    ]) => {
+        $crate::bitfield!{ impl build $bitfield_name [$($param)*] [$($state)* [$crate::Set] [$crate::Set]] [$($rest)*] }
+    };
+    (impl build $bitfield_name:ident [$($param:ident)*] [$($state:tt)*] [
+        (($field_0:ident $($start_0:ident)?) [$(#[doc $($doc_0:tt)*])* #[default $($default_0:tt)*] $($attr_0:tt)*])
+        (($field_1:ident $($start_1:ident)?) [$(#[doc $($doc_1:tt)*])* #[default $($default_1:tt)*] $($attr_1:tt)*])
+        $($rest:tt)*
+    ]) => {
+        $crate::bitfield!{ impl build $bitfield_name [$($param)* $field_0 $field_1] [$($state)* [$field_0] [$field_1]] [$($rest)*] }
+    };
+    (impl build $bitfield_name:ident [$($param:ident)*] [$($state:tt)*] [
+        (($field_0:ident $($start_0:ident)?) [$(#[doc $($doc_0:tt)*])*])
+        (($field_1:ident $($start_1:ident)?) [$(#[doc $($doc_1:tt)*])* #[default $($default_1:tt)*] $($attr_1:tt)*])
+        $($rest:tt)*
+    ]) => {
+        $crate::bitfield!{ impl build $bitfield_name [$($param)* $field_1] [$($state)* [$crate::Set] [$field_1]] [$($rest)*] }
+    };
+    (impl build $bitfield_name:ident [$($param:ident)*] [$($state:tt)*] [
+        (($field_0:ident $($start_0:ident)?) [$(#[doc $($doc_0:tt)*])* #[default $($default_0:tt)*] $($attr_0:tt)*])
+        (($field_1:ident $($start_1:ident)?) [$(#[doc $($doc_1:tt)*])*])
+        $($rest:tt)*
+    ]) => {
+        $crate::bitfield!{ impl build $bitfield_name [$($param)* $field_0] [$($state)* [$field_0] [$crate::Set]] [$($rest)*] }
+    };
+    // Skipped fields (`_`) aren't part of the builder
+    (impl build $bitfield_name:ident [$($param:ident)*] [$($state:tt)*] [(() $attrs:tt) $($rest:tt)*]) => {
+        $crate::bitfield!{ impl build $bitfield_name [$($param)*] [$($state)*] [$($rest)*] }
+    };
+    (impl build $bitfield_name:ident [$($param:ident)*] [$($state:tt)*] [
+        (($field:ident $($start:ident)?) [$(#[doc $($doc:tt)*])* #[default $($default:tt)*] $($attr:tt)*])
+        $($rest:tt)*
+    ]) => {
+        $crate::bitfield!{ impl build $bitfield_name [$($param)* $field] [$($state)* [$field]] [$($rest)*] }
+    };
+    // Fields with other attributes are reported by `impl field_attrs`
+    (impl build $bitfield_name:ident [$($param:ident)*] [$($state:tt)*] [(($field:ident $($start:ident)?) $attrs:tt) $($rest:tt)*]) => {
+        $crate::bitfield!{ impl build $bitfield_name [$($param)*] [$($state)* [$crate::Set]] [$($rest)*] }
+    };
+    (impl build $bitfield_name:ident [$($param:ident)*] [$([$($state:tt)*])*] []) => {
+        #[allow(non_camel_case_types)]
+        impl<$($param),*> Builder<$($($state)*),*> {
+            /// Creates the bitfield. Only exists once all fields without a default value have been set.
+            pub fn build(self) -> $bitfield_name {
+                $bitfield_name(self.raw)
+            }
+        }
+    };
+
+    // Four setters per step, so that bitfields with many fields don't hit the recursion limit
+    (impl builder_setters $typed:tt $bitfield_name:ident [$($before:ident)*] [
+        ($field_0:ident $visibility_0:tt) ($field_1:ident $visibility_1:tt) ($field_2:ident $visibility_2:tt) ($field_3:ident $visibility_3:tt)
+        $(($after:ident $after_visibility:tt))*
+    ]) => {
+        $crate::bitfield!{ impl module_item builder_setter $visibility_0 [$($before)*] [$field_1 $field_2 $field_3 $($after)*] $typed $bitfield_name }
+        $crate::bitfield!{ impl module_item builder_setter $visibility_1 [$($before)* $field_0] [$field_2 $field_3 $($after)*] $typed $bitfield_name }
+        $crate::bitfield!{ impl module_item builder_setter $visibility_2 [$($before)* $field_0 $field_1] [$field_3 $($after)*] $typed $bitfield_name }
+        $crate::bitfield!{ impl module_item builder_setter $visibility_3 [$($before)* $field_0 $field_1 $field_2] [$($after)*] $typed $bitfield_name }
+        $crate::bitfield!{ impl builder_setters $typed $bitfield_name [$($before)* $field_0 $field_1 $field_2 $field_3] [$(($after $after_visibility))*] }
+    };
+    (impl builder_setters $typed:tt $bitfield_name:ident [$($before:ident)*] [($field:ident $visibility:tt) $(($after:ident $after_visibility:tt))*]) => {
+        $crate::bitfield!{ impl module_item builder_setter $visibility [$($before)*] [$($after)*] $typed $bitfield_name }
+        $crate::bitfield!{ impl builder_setters $typed $bitfield_name [$($before)* $field] [$(($after $after_visibility))*] }
+    };
+    (impl builder_setters $typed:tt $bitfield_name:ident [$($before:ident)*] []) => {};
+
+    (impl builder_setter [$($visibility:tt)*] $field:ident [$($before:ident)*] [$($after:ident)*] $typed:tt $bitfield_name:ident) => {
+        #[allow(non_camel_case_types)]
+        impl<$($before,)* $($after,)*> Builder<$($before,)* $crate::Unset, $($after,)*> {
+            /// Sets the field's value. If the value is wider than the field, its lowest bits will be used.
+            /// Each field can only be set once.
+            $($visibility)* fn $field(
+                self, new_value: $crate::bitfield!(impl value_type $typed [$field] [$bitfield_name])
+            ) -> Builder<$($before,)* $crate::Set, $($after,)*> {
+                use $crate::Field;
+                Builder {
+                    raw: (self.raw & !$field::POSITIONED_MASK)
+                        | (($crate::bitfield!(impl from_value $typed new_value) & $field::MASK) << $field::OFFSET),
+                    state: core::marker::PhantomData
+                }
+            }
+        }
+    };
+
+    // The values are converted from a tuple, which only works for up to 12 fields, so larger groups only get the check, which fails
+    (impl group_item [$($visibility:tt)*] $group:ident [$member_0:ident, $member_1:ident, $member_2:ident, $member_3:ident, $member_4:ident, $member_5:ident, $member_6:ident, $member_7:ident, $member_8:ident, $member_9:ident, $member_10:ident, $member_11:ident, $($member:ident),+] $bitfield_type:ty) => {
+        #[allow(non_camel_case_types)]
+        $($visibility)* struct $group(());
+
+        const _: () = $crate::check_group(
+            __FIELD_SPEC, &[stringify!($member_0), stringify!($member_1), stringify!($member_2), stringify!($member_3), stringify!($member_4), stringify!($member_5), stringify!($member_6), stringify!($member_7), stringify!($member_8), stringify!($member_9), stringify!($member_10), stringify!($member_11), $(stringify!($member)),+], stringify!($group), stringify!($bitfield_type)
+        );
+    };
+
+    (impl group_item [$($visibility:tt)*] $group:ident [$($member:ident),+] $bitfield_type:ty) => {
+        /// A group of the bitfield's fields. Can't be constructed outside of a bitfield.
+        ///
+        /// It's actually a struct of size ZERO and implements `FieldGroup<UnderlyingBitfieldType>`, so that the values
+        /// of all its fields can be obtained with `get()` and changed at once with `set()`.
+        #[allow(non_camel_case_types)]
+        $($visibility)* struct $group(());
+
+        // Check that the group's members are fields of the bitfield
+        const _: () = $crate::check_group(
+            __FIELD_SPEC, &[$(stringify!($member)),+], stringify!($group), stringify!($bitfield_type)
+        );
+
+        #[allow(dead_code)]
+        impl $crate::FieldGroup<$bitfield_type> for $group {
+            type Values = ($($crate::bitfield!(impl base_type $bitfield_type [$member]),)+);
+
+            const POSITIONED_MASK: <$bitfield_type as $crate::Bitfield>::BaseType =
+                0 $(| <$member as $crate::Field<$bitfield_type>>::POSITIONED_MASK)+;
+
+            fn get(&self) -> Self::Values {
+                use $crate::Field;
+                let raw = unsafe { *(self as *const Self as *const <$bitfield_type as $crate::Bitfield>::BaseType) };
+
+                ($((raw >> $member::OFFSET) & $member::MASK,)+)
+            }
+
+            fn set(&mut self, values: Self::Values) {
+                use $crate::Field;
+
+                // Can't bind the values to the members' names (they're types), so go through an array
+                let values: [_; [$(stringify!($member)),+].len()] = values.into();
+                let fields = [$(($member::OFFSET, $member::MASK)),+];
+
+                let mut new_value = 0;
+                for (&(offset, mask), &value) in fields.iter().zip(values.iter()) {
+                    new_value |= (value & mask) << offset;
+                }
+
+                let raw = unsafe { &mut *(self as *mut Self as *mut <$bitfield_type as $crate::Bitfield>::BaseType) };
+                *raw = (*raw & !Self::POSITIONED_MASK) | new_value;
+            }
+        }
+    };
+
+    // Each named field is passed as `(name)` or `(name pub)`, see `impl builder`
+    (impl field_indices [$(($field:ident $($start:ident)?))*]) => {
+        #[allow(non_camel_case_types)]
+        enum __FieldIndex {
+            $($field),*
+        }
+    };
+
+    (impl base_type $bitfield_type:ty [$member:ident]) => { <$bitfield_type as $crate::Bitfield>::BaseType };
+
+    (impl spec $bitfield_name:ident [pub $field:ident] $($rest:tt)*) => { $crate::bitfield!(impl spec $bitfield_name [$field] $($rest)*) };
+    (impl spec $bitfield_name:ident [$field:ident] [$($offset:tt)?] [$size:expr]) => {
+        $crate::FieldSpec::new(
+            stringify!($bitfield_name), stringify!($field),
+            $crate::bitfield!(impl width $size), $crate::bitfield!(impl offset [$($offset)?])
+        )
+    };
+
+    (impl spec $bitfield_name:ident [] [$($offset:tt)?] [$size:expr]) => {
+        $crate::FieldSpec::new(
+            stringify!($bitfield_name), "_",
+            $crate::bitfield!(impl width $size), $crate::bitfield!(impl offset [$($offset)?])
+        )
+    };
+
+    // The field's explicit offset (`field @ offset: size`), if any
+    (impl offset []) => { core::option::Option::None };
+    (impl offset [$offset:tt]) => { core::option::Option::Some($crate::bitfield!(impl width $offset)) };
+
+    // A size or offset of any integer type, converted without truncation so that `FieldSpec::new` can check it
+    (impl width $value:expr) => { $crate::Width::new(&($value), ($value) as i128, ($value) as u128) };
+
+    (impl field_type $bitfield_name:ident [pub $field:ident]) => { $bitfield_name::$field };
+    (impl field_type $bitfield_name:ident [$field:ident]) => { $bitfield_name::$field };
+
+    // Can't just refer to `self` because it's a keyword, so have to pass it from call site
+    (impl debug_entry $self:ident [pub $field:ident]) => { $crate::bitfield!(impl debug_entry $self [$field]) };
+    (impl debug_entry $self:ident [$field:ident]) => { core::option::Option::Some((stringify!($field), $crate::Field::get(&$self.$field))) };
+    (impl debug_entry $self:ident []) => { core::option::Option::None };
+
+    (impl visit_entry $self:ident $visitor:ident [pub $field:ident]) => { $crate::bitfield!(impl visit_entry $self $visitor [$field]) };
+    (impl visit_entry $self:ident $visitor:ident [$field:ident]) => { $visitor.visit(stringify!($field), &$self.$field) };
+    (impl visit_entry $self:ident $visitor:ident []) => {};
+
+    // The method of `Writer` that sets the field, with the field's own visibility
+    (impl write_method [pub $(($($restriction:tt)*))? $field:ident] $typed:tt $bitfield_name:ident) => {
+        $crate::bitfield!{ impl write_method_item [pub $(($($restriction)*))?] $field $typed $bitfield_name }
+    };
+    (impl write_method [$field:ident] $typed:tt $bitfield_name:ident) => {
+        $crate::bitfield!{ impl write_method_item [] $field $typed $bitfield_name }
+    };
+    (impl write_method [] $typed:tt $bitfield_name:ident) => {};
+
+    (impl write_method_item [$($visibility:tt)*] $field:ident $typed:tt $bitfield_name:ident) => {
+        /// Sets the field's new value. If the value is wider than the field, its lowest bits will be used.
+        $($visibility)* fn $field(
+            mut self, new_value: $crate::bitfield!(impl value_type $typed [$bitfield_name::$field] [$bitfield_name::$bitfield_name])
+        ) -> Self {
+            use $crate::Field;
+            type ThisField = $bitfield_name::$field;
+            // Setting a field twice keeps the last value
+            self.mask |= ThisField::POSITIONED_MASK;
+            self.value = (self.value & !ThisField::POSITIONED_MASK)
+                | (($crate::bitfield!(impl from_value $typed new_value) & ThisField::MASK) << ThisField::OFFSET);
+            self
+        }
+    };
 }
 
 
+
 #[cfg(doc)]
 bitfield! {
+    #[builder]
     pub struct TestBitfield<u32> {
-        field_1: 2,
+        pub field_1: 2,
         _: 3,
-        field_2: 5
+        pub field_2: 5
     }
 }
 
+/// The union of the [positioned masks](Field::POSITIONED_MASK) of some fields of a bitfield,
+/// computed at compile-time.
+///
+/// Example:
+/// ```
+/// use simple_bitfield::mask_of;
+///
+/// mod registers {
+///     use simple_bitfield::bitfield;
+///
+///     bitfield! {
+///         pub struct Status<u8> {
+///             pub ready: 1,
+///             pub error: 1,
+///             _: 2,
+///             pub code: 4
+///         }
+///     }
+/// }
+///
+/// // Usable in constants
+/// const READY_OR_ERROR: u8 = mask_of!(registers::Status: ready | error);
+///
+/// fn main() {
+///     assert_eq!(READY_OR_ERROR, 0b0000_0011);
+///     assert_eq!(mask_of!(registers::Status: code), 0b1111_0000);
+/// }
+/// ```
+#[macro_export]
+macro_rules! mask_of {
+    ($($bitfield:ident)::+ : $($field:ident)|+) => {{
+        // Can't repeat the path once per field, so give it a name
+        use $($bitfield)::+ as __bitfield;
+
+        const MASK: <__bitfield::__Bitfield as $crate::Bitfield>::BaseType =
+            0 $(| <__bitfield::$field as $crate::Field<__bitfield::__Bitfield>>::POSITIONED_MASK)+;
+        MASK
+    }};
+}
+/// Creates a bitfield from the values of its fields, like a struct literal.
+/// The fields that aren't listed get their [default values](Field::DEFAULT).
+///
+/// The values can be any expressions. `from_fields!` can be used in constants and `const fn`s,
+/// and checks that every value fits into its field:
+/// - literal values that don't fit are always compile-time errors;
+/// - other values that don't fit are compile-time errors in const contexts (like the value of a `const`),
+///   and panic otherwise.
+///
+/// Example:
+/// ```
+/// use simple_bitfield::{ bitfield, from_fields, Field };
+///
+/// bitfield! {
+///     struct Control<u16> {
+///         enable: 1,
+///         /// Clock divider
+///         #[default(4)]
+///         divider: 4,
+///         _: 3,
+///         mode: 2
+///     }
+/// }
+///
+/// const FAST: Control::Control = from_fields!(Control { enable: 1, mode: 3 });
+///
+/// // Panics if `mode` doesn't fit into 2 bits
+/// fn enabled(mode: u16) -> Control::Control {
+///     from_fields!(Control { enable: 1, mode: mode })
+/// }
+///
+/// fn main() {
+///     assert_eq!((FAST.enable.get(), FAST.divider.get(), FAST.mode.get()), (1, 4, 3));
+///
+///     let slow = from_fields!(Control { divider: 15 });
+///     assert_eq!(u16::from(slow), 0b11110);
+///
+///     assert_eq!(enabled(3), FAST);
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use simple_bitfield::{ bitfield, from_fields };
+///
+/// bitfield! {
+///     struct Control<u16> {
+///         enable: 1,
+///         divider: 4
+///     }
+/// }
+///
+/// # fn main() {
+/// // Error: the value of field `divider` doesn't fit into it
+/// let control = from_fields!(Control { divider: 16 });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! from_fields {
+    ($($bitfield:ident)::+ { $($fields:tt)* }) => {{
+        // The listed fields replace their defaults
+        let pattern = $crate::__field_pattern!($($bitfield)::+ { $($fields)* });
+
+        $($bitfield)::+::new((<$($bitfield)::+::__Bitfield as $crate::Bitfield>::DEFAULTS & !pattern.0) | pattern.1)
+    }};
+}
+
+/// Computes `(mask, value)` for `__field_pattern!(path::to::Bitfield { field: value, ... })`:
+/// the union of the listed fields' positioned masks, and their values shifted into place.
+/// Fails to compile if a field is listed twice. Literal values that don't fit into their field are compile-time errors,
+/// other values panic if they don't fit (which is a compile-time error as well in const contexts).
+/// Used by [from_fields] and [bf_match].
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __field_pattern {
+    /* Each field is collected as `(field value)`, where `value` is a single token tree:
+     * literals stay literals, so that they can be checked at compile-time, and other expressions are parenthesized.
+     * Several fields are collected at a time to keep the recursion shallow: four values that are a single token tree each,
+     * or two values of which the first isn't a literal.
+     */
+    (impl [$($path:tt)*] [$($fields:tt)*]
+        $field1:ident : $value1:tt, $field2:ident : $value2:tt, $field3:ident : $value3:tt, $field4:ident : $value4:tt $(, $($rest:tt)*)?
+    ) => {
+        $crate::__field_pattern!(
+            impl [$($path)*] [$($fields)* ($field1 $value1) ($field2 $value2) ($field3 $value3) ($field4 $value4)] $($($rest)*)?
+        )
+    };
+    (impl [$($path:tt)*] [$($fields:tt)*] $field:ident : $value:literal $(, $($rest:tt)*)?) => {
+        $crate::__field_pattern!(impl [$($path)*] [$($fields)* ($field $value)] $($($rest)*)?)
+    };
+    (impl [$($path:tt)*] [$($fields:tt)*] $field1:ident : $value1:expr, $field2:ident : $value2:literal $(, $($rest:tt)*)?) => {
+        $crate::__field_pattern!(impl [$($path)*] [$($fields)* ($field1 ($value1)) ($field2 $value2)] $($($rest)*)?)
+    };
+    (impl [$($path:tt)*] [$($fields:tt)*] $field1:ident : $value1:expr, $field2:ident : $value2:expr $(, $($rest:tt)*)?) => {
+        $crate::__field_pattern!(impl [$($path)*] [$($fields)* ($field1 ($value1)) ($field2 ($value2))] $($($rest)*)?)
+    };
+    (impl [$($path:tt)*] [$($fields:tt)*] $field:ident : $value:expr $(,)?) => {
+        $crate::__field_pattern!(impl [$($path)*] [$($fields)* ($field ($value))])
+    };
+
+    (impl [$($bitfield:ident)::+] [$(($field:ident $value:tt))*]) => {{
+        // Can't repeat the path once per field, so give it a name
+        use $($bitfield)::+ as __bitfield;
+        type BaseType = <__bitfield::__Bitfield as $crate::Bitfield>::BaseType;
+
+        // Fields can't be listed twice, just like in a struct literal or pattern
+        #[allow(dead_code)]
+        struct Fields { $($field: ()),* }
+
+        #[allow(unused_mut)]
+        let (mut mask, mut values): (BaseType, BaseType) = (0, 0);
+        $(
+            let value = $crate::__field_pattern!(impl value $field $value);
+
+            mask |= <__bitfield::$field as $crate::Field<__bitfield::__Bitfield>>::POSITIONED_MASK;
+            values |= value << <__bitfield::$field as $crate::Field<__bitfield::__Bitfield>>::OFFSET;
+        )*
+
+        (mask, values)
+    }};
+
+    // Literal values are checked in a constant, so that they're checked at compile-time even outside of const contexts
+    (impl value $field:ident $value:literal) => {{
+        const VALUE: BaseType = $crate::__field_pattern!(impl value $field ($value));
+        VALUE
+    }};
+    (impl value $field:ident $value:expr) => {{
+        let value: BaseType = $value;
+
+        #[allow(clippy::bad_bit_mask)]
+        let fits = value & !<__bitfield::$field as $crate::Field<__bitfield::__Bitfield>>::MASK == 0;
+        if !fits {
+            panic!(concat!("the value of field `", stringify!($field), "` doesn't fit into it"))
+        }
+
+        value
+    }};
+
+    ($($bitfield:ident)::+ { $($fields:tt)* }) => {
+        $crate::__field_pattern!(impl [$($bitfield)::+] [] $($fields)*)
+    };
+}
+
+/// Tests whether a bitfield's fields have the given values, ignoring the other fields, like `matches!`.
+///
+/// `bf_match!(value, Bitfield { field: x, other_field: y })` is `true` if `field` is `x` and `other_field` is `y`.
+/// The mask and the expected value of the pattern are computed at compile-time, so the test is a single AND and a comparison,
+/// and values that don't fit into their fields are compile-time errors.
+///
+/// `bf_match!(value, { Bitfield { ... } => expression, ..., _ => expression })` tests the patterns in order,
+/// like `match`, and evaluates to the expression of the first pattern that matches. The last arm must be `_`.
+///
+/// Example:
+/// ```
+/// use simple_bitfield::{ bitfield, bf_match };
+///
+/// bitfield! {
+///     struct Insn<u32> {
+///         opcode: 7,
+///         rd: 5,
+///         funct3: 3,
+///         rs1: 5,
+///         rs2: 5,
+///         funct7: 7
+///     }
+/// }
+///
+/// const OP: u32 = 0b0110011;
+///
+/// fn name(insn: Insn::Insn) -> &'static str {
+///     bf_match!(insn, {
+///         Insn { opcode: OP, funct3: 0, funct7: 0 } => "add",
+///         Insn { opcode: OP, funct3: 0, funct7: 0b0100000 } => "sub",
+///         Insn { opcode: 0b0010011, funct3: 0 } => "addi",
+///         _ => "unknown"
+///     })
+/// }
+///
+/// fn main() {
+///     let add = Insn::new(0x00B5_0533); // add a0, a0, a1
+///     let sub = Insn::new(0x40B5_0533); // sub a0, a0, a1
+///
+///     assert!(bf_match!(add, Insn { opcode: OP, funct3: 0 }));
+///     assert!(!bf_match!(add, Insn { opcode: OP, funct3: 1 }));
+///
+///     assert_eq!(name(add), "add");
+///     assert_eq!(name(sub), "sub");
+///     assert_eq!(name(Insn::new(0)), "unknown");
+/// }
+/// ```
+///
+/// ```compile_fail
+/// use simple_bitfield::{ bitfield, bf_match };
+///
+/// bitfield! {
+///     struct Insn<u32> {
+///         opcode: 7,
+///         rd: 5
+///     }
+/// }
+///
+/// # fn main() {
+/// // Error: the value of field `opcode` doesn't fit into it
+/// let is_op = bf_match!(Insn::new(0), Insn { opcode: 0b1_0000000 });
+/// # }
+/// ```
+#[macro_export]
+macro_rules! bf_match {
+    // The arms are tested one by one; `$bitfield_ref` is a reference to the tested bitfield
+    (impl arms $bitfield_ref:ident _ => $default:expr $(,)?) => { $default };
+    (impl arms $bitfield_ref:ident $($bitfield:ident)::+ { $($fields:tt)* } => $arm:expr $(,)?) => {
+        compile_error!("the last arm of `bf_match!` must be `_ => ...`")
+    };
+    (impl arms $bitfield_ref:ident $($bitfield:ident)::+ { $($fields:tt)* } => $arm:expr, $($rest:tt)*) => {
+        if $crate::bf_match!(impl test $bitfield_ref $($bitfield)::+ { $($fields)* }) {
+            $arm
+        } else {
+            $crate::bf_match!(impl arms $bitfield_ref $($rest)*)
+        }
+    };
+
+    (impl test $bitfield_ref:ident $($bitfield:ident)::+ { $($fields:tt)* }) => {{
+        // The mask of the listed fields and their expected values, computed at compile-time
+        type BaseType = <$($bitfield)::+::__Bitfield as $crate::Bitfield>::BaseType;
+        const PATTERN: (BaseType, BaseType) = $crate::__field_pattern!($($bitfield)::+ { $($fields)* });
+
+        // Also checks that the bitfield has the pattern's type
+        let bitfield: &$($bitfield)::+::__Bitfield = $bitfield_ref;
+        *<$($bitfield)::+::__Bitfield as $crate::Bitfield>::raw_ref(bitfield) & PATTERN.0 == PATTERN.1
+    }};
+
+    ($value:expr, { $($arms:tt)* }) => {{
+        let bitfield = &$value;
+        $crate::bf_match!(impl arms bitfield $($arms)*)
+    }};
+    ($value:expr, $($bitfield:ident)::+ { $($fields:tt)* }) => {{
+        let bitfield = &$value;
+        $crate::bf_match!(impl test bitfield $($bitfield)::+ { $($fields)* })
+    }};
+}
 
 // Should be AFTER the macro definition
 #[cfg(test)]
@@ -0,0 +1,149 @@
+//! Comparisons that only consider some fields of a bitfield, see [Masked] and [Bitfield::eq_fields].
+
+use core::{
+    fmt::{ self, Debug },
+    hash::{ Hash, Hasher },
+    marker::PhantomData,
+    ops::Deref
+};
+use crate::{ Bitfield, Field, BitStorage };
+
+/// A set of fields of the bitfield `B`, chosen at compile-time: [NamedFields] or a tuple of up to 12 fields.
+pub trait FieldSet<B: Bitfield> {
+    /// The bits covered by the fields of the set.
+    fn mask() -> B::BaseType;
+}
+
+/// All named fields of a bitfield: every bit except the skipped ones (`_`) and the ones after the last field.
+#[derive(Copy, Clone, Debug)]
+pub struct NamedFields;
+
+impl<B: Bitfield> FieldSet<B> for NamedFields {
+    fn mask() -> B::BaseType {
+        B::USED_MASK
+    }
+}
+
+macro_rules! impl_field_set {
+    ($(($($field:ident),+))*) => {$(
+        impl<B: Bitfield, $($field: Field<B>),+> FieldSet<B> for ($($field,)+) {
+            fn mask() -> B::BaseType {
+                let mut mask = B::BaseType::ZERO;
+                $(mask |= $field::POSITIONED_MASK;)+
+
+                mask
+            }
+        }
+    )*};
+}
+
+impl_field_set! {
+    (F0)
+    (F0, F1)
+    (F0, F1, F2)
+    (F0, F1, F2, F3)
+    (F0, F1, F2, F3, F4)
+    (F0, F1, F2, F3, F4, F5)
+    (F0, F1, F2, F3, F4, F5, F6)
+    (F0, F1, F2, F3, F4, F5, F6, F7)
+    (F0, F1, F2, F3, F4, F5, F6, F7, F8)
+    (F0, F1, F2, F3, F4, F5, F6, F7, F8, F9)
+    (F0, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10)
+    (F0, F1, F2, F3, F4, F5, F6, F7, F8, F9, F10, F11)
+}
+
+/// A bitfield whose `PartialEq` and `Hash` only consider the fields in `S`, so that bitfields that
+/// differ only in other bits (reserved bits, "don't care" fields) are equal and hash the same.
+///
+/// Example:
+/// ```
+/// use simple_bitfield::{ bitfield, Field, Masked, NamedFields };
+/// use std::collections::HashSet;
+///
+/// bitfield! {
+///     struct Status<u8> {
+///         ready: 1,
+///         error: 1,
+///         _: 2,
+///         counter: 4
+///     }
+/// }
+///
+/// fn main() {
+///     let a = Status::new(0b0001_00_01);
+///     let b = Status::new(0b0111_11_01);
+///
+///     // Only the skipped bits differ
+///     assert_ne!(a, Status::new(0b0001_11_01));
+///     assert_eq!(Masked::<_, NamedFields>::new(a), Masked::new(Status::new(0b0001_11_01)));
+///
+///     // Only `ready` and `error` count
+///     let mut seen = HashSet::new();
+///     seen.insert(Masked::<_, (Status::ready, Status::error)>::new(a));
+///     assert!(seen.contains(&Masked::new(b)));
+///     assert_eq!(seen.iter().next().unwrap().counter.get(), 0b0001);
+/// }
+/// ```
+pub struct Masked<B: Bitfield, S: FieldSet<B> = NamedFields> {
+    bitfield: B,
+    fields: PhantomData<fn() -> S>
+}
+
+impl<B: Bitfield, S: FieldSet<B>> Masked<B, S> {
+    /// Wraps the bitfield.
+    pub fn new(bitfield: B) -> Self {
+        Masked { bitfield, fields: PhantomData }
+    }
+
+    /// Returns the wrapped bitfield.
+    pub fn into_inner(self) -> B {
+        self.bitfield
+    }
+
+    /// The bits that are compared and hashed.
+    pub fn mask() -> B::BaseType {
+        S::mask()
+    }
+}
+
+impl<B: Bitfield, S: FieldSet<B>> Deref for Masked<B, S> {
+    type Target = B;
+
+    fn deref(&self) -> &B {
+        &self.bitfield
+    }
+}
+
+impl<B: Bitfield, S: FieldSet<B>> From<B> for Masked<B, S> {
+    fn from(bitfield: B) -> Self {
+        Self::new(bitfield)
+    }
+}
+
+impl<B: Bitfield + Clone, S: FieldSet<B>> Clone for Masked<B, S> {
+    fn clone(&self) -> Self {
+        Self::new(self.bitfield.clone())
+    }
+}
+
+impl<B: Bitfield + Copy, S: FieldSet<B>> Copy for Masked<B, S> {}
+
+impl<B: Bitfield + Debug, S: FieldSet<B>> Debug for Masked<B, S> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("Masked").field(&self.bitfield).finish()
+    }
+}
+
+impl<B: Bitfield, S: FieldSet<B>> PartialEq for Masked<B, S> {
+    fn eq(&self, other: &Self) -> bool {
+        self.bitfield.eq_masked(&other.bitfield, S::mask())
+    }
+}
+
+impl<B: Bitfield, S: FieldSet<B>> Eq for Masked<B, S> {}
+
+impl<B: Bitfield, S: FieldSet<B>> Hash for Masked<B, S> where B::BaseType: Hash {
+    fn hash<H: Hasher>(&self, hasher: &mut H) {
+        (*self.bitfield.raw_ref() & S::mask()).hash(hasher)
+    }
+}
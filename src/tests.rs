@@ -1,7 +1,7 @@
 // This is needed for tests: https://stackoverflow.com/questions/28185854/how-do-i-test-crates-with-no-std
 extern crate std;
 
-use super::{bitfield, Field, Bitfield};
+use super::{Field, Bitfield, FieldInfo, Access, FieldVisitor, FieldError, FieldDescriptor, DynField, BitRange, FieldGroup, FieldValue, Masked, NamedFields};
 use core::mem::{size_of, size_of_val};
 
 bitfield! {
@@ -25,6 +25,200 @@ bitfield! {
     }
 }
 
+const ADDR_BITS: u8 = 20;
+const FLAG_BITS: usize = 2;
+
+bitfield! {
+    struct Descriptor<u32> {
+        flags: FLAG_BITS,
+        addr: ADDR_BITS,
+        tag: 32 - ADDR_BITS - FLAG_BITS as u8
+    }
+
+    // Sizes and offsets can be of any integer type
+    struct AnyWidths<u16> {
+        a: 3i64,
+        b @ 8u128: 4isize
+    }
+
+    // Listed in a different order than declared
+    #[group(trim: fine, coarse)]
+    #[group(everything: all_of_it)]
+    struct Oscillator<u16> {
+        coarse: 4,
+        _: 4,
+        fine: 6,
+        all_of_it: 2
+    }
+
+    #[exhaustive]
+    struct FullWidth<u16> {
+        all: 16
+    }
+
+    /// An instruction's header.
+    ///
+    /// Layout:
+    ///  * bits 0..4: opcode;
+    ///  * bits 4..6: reserved;
+    ///  * bits 6..8: number of operands.
+    ///
+    /// All bits are accounted for,
+    #[exhaustive]
+    /// so this bitfield is exhaustive.
+    #[must_use]
+    struct Header<u8> {
+        opcode: 4,
+        _: 2,
+        operands: 2
+    }
+
+    #[builder]
+    struct Defaults<u16> {
+        /// Documented, without a default
+        plain: 4,
+        #[default(0b101)]
+        /// Documented after the default
+        three: 3,
+        /// Reserved
+        _: 1,
+        #[default(u8::MAX as u16)]
+        byte: 8
+    }
+
+    #[typed]
+    #[builder]
+    struct Typed<u16> {
+        #[default(2)]
+        mode: 2,
+        _: 2,
+        divider: 12
+    }
+
+    // Compared by hand below, ignoring the reserved bits
+    #[no_compare]
+    struct Status<u8> {
+        ready: 1,
+        _: 7
+    }
+
+    // The sign bit doesn't belong to any field
+    struct Signed<i8> {
+        low: 3,
+        high: 3
+    }
+
+    // Fields of signed types that cover the sign bit
+    struct WithSign<i8> {
+        low: 7,
+        sign: 1
+    }
+
+    struct AllSigned<i16> {
+        all: 16
+    }
+
+    struct Sparse<u16> {
+        low: 4,
+        high @ 12: 4,
+        _ @ 4: 2,
+        mid: 2
+    }
+}
+
+// 128 single-bit fields must not need a `#![recursion_limit]` bump, even with a builder
+bitfield! {
+    #[builder]
+    // As many fields as a group can have
+    #[group(low_bits: b11, b10, b9, b8, b7, b6, b5, b4, b3, b2, b1, b0)]
+    struct Wide<u128> {
+        b0: 1, b1: 1, b2: 1, b3: 1, b4: 1, b5: 1, b6: 1, b7: 1,
+        b8: 1, b9: 1, b10: 1, b11: 1, b12: 1, b13: 1, b14: 1, b15: 1,
+        b16: 1, b17: 1, b18: 1, b19: 1, b20: 1, b21: 1, b22: 1, b23: 1,
+        b24: 1, b25: 1, b26: 1, b27: 1, b28: 1, b29: 1, b30: 1, b31: 1,
+        b32: 1, b33: 1, b34: 1, b35: 1, b36: 1, b37: 1, b38: 1, b39: 1,
+        b40: 1, b41: 1, b42: 1, b43: 1, b44: 1, b45: 1, b46: 1, b47: 1,
+        b48: 1, b49: 1, b50: 1, b51: 1, b52: 1, b53: 1, b54: 1, b55: 1,
+        b56: 1, b57: 1, b58: 1, b59: 1, b60: 1, b61: 1, b62: 1, b63: 1,
+        b64: 1, b65: 1, b66: 1, b67: 1, b68: 1, b69: 1, b70: 1, b71: 1,
+        b72: 1, b73: 1, b74: 1, b75: 1, b76: 1, b77: 1, b78: 1, b79: 1,
+        b80: 1, b81: 1, b82: 1, b83: 1, b84: 1, b85: 1, b86: 1, b87: 1,
+        b88: 1, b89: 1, b90: 1, b91: 1, b92: 1, b93: 1, b94: 1, b95: 1,
+        b96: 1, b97: 1, b98: 1, b99: 1, b100: 1, b101: 1, b102: 1, b103: 1,
+        b104: 1, b105: 1, b106: 1, b107: 1, b108: 1, b109: 1, b110: 1, b111: 1,
+        b112: 1, b113: 1, b114: 1, b115: 1, b116: 1, b117: 1, b118: 1, b119: 1,
+        b120: 1, b121: 1, b122: 1, b123: 1, b124: 1, b125: 1, b126: 1, b127: 1
+    }
+}
+
+impl PartialEq for Status::Status {
+    fn eq(&self, other: &Self) -> bool {
+        self.ready.get() == other.ready.get()
+    }
+}
+
+mod registers {
+    use super::Field;
+
+    bitfield! {
+        #[group(pub(super) config: mode, level)]
+        #[group(state: enable, locked)]
+        #[builder]
+        pub struct Control<u8> {
+            pub enable: 1,
+            pub(crate) mode: 2,
+            pub(super) level: 3,
+            locked: 1
+        }
+    }
+
+    pub fn is_locked(control: &Control::Control) -> bool {
+        control.locked.is_set()
+    }
+
+    pub fn locked(mode: u8, level: u8) -> Control::Control {
+        Control::builder().mode(mode).enable(1).level(level).locked(1).build()
+    }
+
+    pub fn state(control: &Control::Control) -> (u8, u8) {
+        use crate::FieldGroup;
+
+        control.state.get()
+    }
+}
+
+// Items that shadow names from the prelude where `bitfield!` is invoked
+#[allow(dead_code)]
+mod shadowed_prelude {
+    pub struct Option;
+    pub struct Some;
+    pub struct None;
+    pub trait From {}
+    pub trait PartialEq {}
+    pub trait PartialOrd {}
+    pub trait Ord {}
+
+    const MODE_BITS: u32 = 2;
+    const LEVEL_OFFSET: u8 = 4;
+    const DEFAULT_LEVEL: u8 = 5;
+
+    bitfield! {
+        pub struct Shadowed<u8> {
+            pub mode: MODE_BITS,
+            #[default(DEFAULT_LEVEL)]
+            pub level @ LEVEL_OFFSET: 3
+        }
+    }
+}
+
+// Fields named like the bitfield's methods, and like each other's accessors
+bitfield! {
+    struct MethodNames<u16> {
+        modify: 1, x: 1, set_x: 1, get_x: 1, clone: 1, eq: 1, cmp: 1, hash: 1, diff: 1, get: 1, set: 1, with: 1, max: 1
+    }
+}
+
+#[allow(clippy::assertions_on_constants)]
 #[test]
 fn validity() {
     assert!(TestBitfield::field1::VALID);
@@ -72,6 +266,7 @@ fn mask() {
     assert_eq!(bf.field3.mask(), TestBitfield::field3::MASK);
 }
 
+#[allow(clippy::unusual_byte_groupings)]
 #[test]
 fn data_get() {
     let val = 0b_1011111111_0110011_11010;
@@ -96,6 +291,7 @@ fn data_get() {
     }
 }
 
+#[allow(clippy::unusual_byte_groupings)]
 #[test]
 fn data_set() {
     let val = 0b_1011111111_0110011_11010;
@@ -129,6 +325,7 @@ fn data_set() {
     }
 }
 
+#[allow(clippy::unusual_byte_groupings)]
 #[test]
 fn data_set_checked() {
     let val = 0b1_1010100_11111;
@@ -146,7 +343,7 @@ fn data_set_checked() {
 
 #[test]
 fn use_in_struct() {
-    #[allow(dead_code)]
+    #[allow(dead_code, clippy::repr_packed_without_abi)]
     #[repr(packed)]
     struct SomeStruct {
         bitfield1: TestBitfield::TestBitfield,
@@ -164,10 +361,12 @@ fn use_in_struct() {
         bitfield2: AnotherOne::new(0b11_000)
     };
 
-    // warning: borrow of packed field is unsafe and requires unsafe function or block (error E0133)
-    // This is because `the_struct.bitfield1` borrows `the_struct` via the `Deref` trait
-    assert_eq!(the_struct.bitfield1.field2.get(), 1);
-    assert_eq!(the_struct.bitfield2.f2.get(), 1)
+    // error: reference to field of packed struct is unaligned (error E0793)
+    // This is because `the_struct.bitfield1` borrows `the_struct` via the `Deref` trait,
+    // so the bitfields have to be copied out of the packed struct first
+    let (bitfield1, bitfield2) = (the_struct.bitfield1, the_struct.bitfield2);
+    assert_eq!(bitfield1.field2.get(), 1);
+    assert_eq!(bitfield2.f2.get(), 1)
 }
 
 #[test]
@@ -189,4 +388,702 @@ fn printing() {
     std::println!("{}", a_bitfield.field1.get());
 
     std::println!("{}\n{:?}", a_bitfield, a_bitfield)
-}
\ No newline at end of file
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn field_visibility() {
+    let mut control = registers::Control::new(0b1_101_10_1);
+
+    assert_eq!(control.enable.get(), 1);
+    assert_eq!(control.mode.get(), 0b10);
+    assert_eq!(control.level.get(), 0b101);
+
+    control.mode.set(0b01);
+    assert_eq!(u8::from(control), 0b1_101_01_1);
+
+    // `control.locked` is private to `registers`
+    assert!(registers::is_locked(&control))
+}
+
+#[test]
+fn const_expression_sizes() {
+    assert_eq!(Descriptor::flags::SIZE, 2);
+    assert_eq!(Descriptor::addr::SIZE, 20);
+    assert_eq!(Descriptor::tag::SIZE, 10);
+    assert_eq!(Descriptor::tag::OFFSET, 22);
+
+    let desc = Descriptor::new(0xABCD_EF01);
+    assert_eq!(desc.addr.get(), (0xABCD_EF01 >> 2) & 0xF_FFFF);
+    assert_eq!(desc.tag.get(), 0xABCD_EF01 >> 22);
+
+    assert_eq!((AnyWidths::a::SIZE, AnyWidths::b::OFFSET, AnyWidths::b::SIZE), (3, 8, 4));
+}
+
+#[test]
+fn explicit_offsets() {
+    assert_eq!(Sparse::low::OFFSET, 0);
+    assert_eq!(Sparse::high::OFFSET, 12);
+    assert_eq!(Sparse::mid::OFFSET, 6);
+
+    let mut sparse = Sparse::new(0b1010_0000_1100_0110);
+    assert_eq!(sparse.low.get(), 0b0110);
+    assert_eq!(sparse.mid.get(), 0b11);
+    assert_eq!(sparse.high.get(), 0b1010);
+
+    sparse.high.set(0b0101);
+    assert_eq!(u16::from(sparse), 0b0101_0000_1100_0110)
+}
+
+#[test]
+fn many_fields() {
+    assert_eq!(Wide::b0::OFFSET, 0);
+    assert_eq!(Wide::b64::OFFSET, 64);
+    assert_eq!(Wide::b127::OFFSET, 127);
+
+    let mut wide = Wide::new(1 << 127 | 1 << 64);
+    assert_eq!(wide.b127.get(), 1);
+    assert_eq!(wide.b64.get(), 1);
+    assert_eq!(wide.b63.get(), 0);
+
+    wide.b127.set(0);
+    wide.b0.set(1);
+    assert_eq!(u128::from(wide), 1 << 64 | 1);
+
+    let debug = std::format!("{:?}", wide);
+    assert!(debug.starts_with("Wide(b0: 1, b1: 0, "));
+    assert!(debug.ends_with(", b126: 0, b127: 0)"))
+}
+
+#[test]
+fn full_width_field() {
+    assert_eq!(FullWidth::all::MASK, u16::MAX);
+
+    let mut full = FullWidth::new(0xBEEF);
+    assert_eq!(full.all.get(), 0xBEEF);
+
+    full.all.set(0x1234);
+    assert_eq!(u16::from(full), 0x1234)
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn exhaustive_bitfield() {
+    let header = Header::new(0b10_11_0101);
+
+    assert_eq!(header.opcode.get(), 0b0101);
+    assert_eq!(header.operands.get(), 0b10);
+    assert_eq!(std::format!("{:?}", header), "Header(opcode: 5, operands: 2)")
+}
+
+#[test]
+fn layout() {
+    fn info(name: &'static str, offset: u8, size: u8, mask: u16) -> FieldInfo<u16> {
+        let reserved = name == "_";
+        let access = if reserved { Access::None } else { Access::ReadWrite };
+
+        FieldInfo { name, offset, size, mask, access, reserved }
+    }
+
+    assert_eq!(Sparse::Sparse::LAYOUT, &[
+        info("low", 0, 4, 0b1111),
+        info("high", 12, 4, 0b1111),
+        info("_", 4, 2, 0b11),
+        info("mid", 6, 2, 0b11)
+    ]);
+
+    assert_eq!(FullWidth::FullWidth::LAYOUT, &[info("all", 0, 16, u16::MAX)]);
+
+    assert_eq!(Wide::Wide::LAYOUT.len(), 128);
+    assert!(Wide::Wide::LAYOUT.iter().enumerate().all(|(i, field)| field.offset as usize == i && field.mask == 1))
+}
+
+#[test]
+fn iterate_fields() {
+    let sparse = Sparse::new(0b1010_0000_1100_0110);
+
+    assert!(sparse.iter_fields().eq([("low", 0b0110), ("high", 0b1010), ("mid", 0b11)]));
+    assert_eq!(Wide::new(u128::MAX).iter_fields().filter(|&(_, value)| value == 1).count(), 128);
+
+    // Collects each field's name, offset and value
+    struct Collect(std::vec::Vec<(&'static str, u8, u16)>);
+
+    impl<B: Bitfield<BaseType=u16>> FieldVisitor<B> for Collect {
+        fn visit<F: Field<B>>(&mut self, name: &'static str, field: &F) {
+            self.0.push((name, F::OFFSET, field.get()))
+        }
+    }
+
+    let mut collect = Collect(std::vec::Vec::new());
+    sparse.visit_fields(&mut collect);
+    assert_eq!(collect.0, [("low", 0, 0b0110), ("high", 12, 0b1010), ("mid", 6, 0b11)]);
+}
+
+#[test]
+fn access_by_name() {
+    let mut sparse = Sparse::new(0b1010_0000_1100_0110);
+
+    assert_eq!(sparse.get_by_name("high"), Some(0b1010));
+    assert_eq!(sparse.get_by_name("mid"), Some(0b11));
+    assert_eq!(sparse.get_by_name("_"), None);
+    assert_eq!(sparse.get_by_name("nope"), None);
+
+    assert_eq!(sparse.set_by_name("high", 0b0101), Ok(()));
+    assert_eq!(sparse.set_by_name("mid", 0b100), Err(FieldError::ValueTooWide { size: 2 }));
+    assert_eq!(sparse.set_by_name("_", 0), Err(FieldError::UnknownField));
+    assert_eq!(u16::from(sparse), 0b0101_0000_1100_0110);
+
+    let mut full = FullWidth::new(0);
+    assert_eq!(full.set_by_name("all", u16::MAX), Ok(()));
+    assert_eq!(full.all.get(), u16::MAX);
+
+    assert_eq!(std::format!("{}", FieldError::ValueTooWide { size: 2 }), "value doesn't fit into the field's 2 bits");
+}
+
+#[test]
+fn dynamic_fields() {
+    let mut sparse = Sparse::new(0);
+
+    let high: FieldDescriptor<Sparse::Sparse> = (&sparse.high).into();
+    assert_eq!(high, Sparse::high::DESCRIPTOR);
+    assert_eq!(sparse.mid.descriptor(), Sparse::mid::DESCRIPTOR);
+    assert_ne!(high, Sparse::low::DESCRIPTOR);
+
+    // Table-driven: each field with the value to write
+    let table: [(&dyn DynField<Sparse::Sparse>, u16); 3] = [
+        (&Sparse::low::DESCRIPTOR, 0b1001),
+        (&high, 0b0110),
+        (&Sparse::mid::DESCRIPTOR, 0b10)
+    ];
+
+    for &(field, value) in table.iter() {
+        field.set(&mut sparse, value);
+        assert_eq!(field.get(&sparse), value);
+    }
+    assert_eq!(u16::from(sparse), 0b0110_0000_1000_1001);
+
+    assert_eq!((high.offset(), high.size(), high.mask()), (12, 4, 0b1111));
+    assert_eq!(table[2].0.set_checked(&mut sparse, 0b111), Err(0b11));
+    assert_eq!(sparse.mid.get(), 0b10);
+
+    let full = FullWidth::all::DESCRIPTOR;
+    let mut full_width = FullWidth::new(0);
+    full.set(&mut full_width, 0xABCD);
+    assert_eq!(full_width.all.get(), 0xABCD);
+}
+
+#[test]
+fn bit_ranges() {
+    let mut value: u32 = 0x1234_5678;
+
+    assert_eq!(BitRange::<0, 4>::get(value), 0x8);
+    assert_eq!(BitRange::<28, 4>::get(value), 0x1);
+    assert_eq!(BitRange::<0, 32>::get(value), value);
+
+    BitRange::<8, 8>::set(&mut value, 0x1AB);
+    assert_eq!(value, 0x1234_AB78);
+    assert_eq!(BitRange::<8, 8>::set_checked(&mut value, 0x100), Err(0));
+    assert_eq!(value, 0x1234_AB78);
+
+    // Same range, different types
+    assert_eq!(BitRange::<4, 4>::get(0xA5u8), 0xA);
+    assert_eq!(BitRange::<124, 4>::get(u128::MAX), 0xF);
+    assert_eq!(BitRange::<0, 3>::get(-1i8), 0b111);
+    assert_eq!(BitRange::<0, 64>::mask::<u64>(), u64::MAX);
+
+    assert!(BitRange::<8, 8>::fits::<u16>());
+    assert!(!BitRange::<9, 8>::fits::<u16>());
+    assert!(!BitRange::<0, 0>::fits::<u16>());
+
+    // Fields and ranges agree
+    let sparse = Sparse::new(0b1010_0000_1100_0110);
+    assert_eq!(BitRange::<12, 4>::get(u16::from(sparse)), sparse.high.get());
+}
+
+#[test]
+fn generic_field_access() {
+    // Works for any field of any bitfield
+    fn fill<B: Bitfield, F: Field<B>>(bitfield: &mut B) {
+        if bitfield.get::<F>() != F::MASK {
+            bitfield.set::<F>(F::MASK)
+        }
+    }
+
+    let mut sparse = Sparse::new(0b0010_0000_1100_0110);
+    fill::<_, Sparse::high>(&mut sparse);
+    assert_eq!(sparse.get::<Sparse::high>(), 0b1111);
+    assert_eq!(sparse.high.get(), 0b1111);
+
+    let sparse = sparse.with::<Sparse::mid>(0b01).with::<Sparse::low>(0b1_0000);
+    assert_eq!(u16::from(sparse), 0b1111_0000_0100_0000);
+
+    let mut control = registers::Control::new(0);
+    fill::<_, registers::Control::mode>(&mut control);
+    assert_eq!(u8::from(control), 0b0000_0110);
+}
+
+#[test]
+fn raw_access() {
+    type Bf = Sparse::Sparse;
+
+    assert_eq!(Bf::NAME, "Sparse");
+    assert_eq!(Bf::FIELD_COUNT, 3);
+    assert_eq!(Bf::USED_MASK, 0b1111_0000_1100_1111);
+    assert_eq!(FullWidth::FullWidth::USED_MASK, u16::MAX);
+    assert_eq!(Wide::Wide::USED_MASK, u128::MAX);
+    assert_eq!(TestBitfield::TestBitfield::FIELD_COUNT, 3);
+
+    let mut sparse = Bf::from_raw(0b1010_0000_1100_0110);
+    assert_eq!(sparse.high.get(), 0b1010);
+
+    *sparse.raw_mut() &= Bf::USED_MASK;
+    assert_eq!(*sparse.raw_ref(), 0b1010_0000_1100_0110);
+
+    *sparse.raw_mut() = 0xFFFF;
+    assert_eq!(sparse.into_raw(), 0xFFFF);
+}
+
+#[test]
+fn masks() {
+    assert_eq!(Sparse::low::POSITIONED_MASK, 0b0000_0000_0000_1111);
+    assert_eq!(Sparse::high::POSITIONED_MASK, 0b1111_0000_0000_0000);
+    assert_eq!(Sparse::mid::POSITIONED_MASK, 0b0000_0000_1100_0000);
+    assert_eq!(Wide::b127::POSITIONED_MASK, 1 << 127);
+
+    assert_eq!(Sparse::Sparse::UNUSED_MASK, 0b0000_1111_0011_0000);
+    assert_eq!(FullWidth::FullWidth::UNUSED_MASK, 0);
+
+    const LOW_AND_HIGH: u16 = mask_of!(Sparse: low | high);
+    assert_eq!(LOW_AND_HIGH, 0b1111_0000_0000_1111);
+    assert_eq!(mask_of!(Sparse: low | high | mid), Sparse::Sparse::USED_MASK);
+    assert_eq!(mask_of!(self::registers::Control: enable | mode), 0b0000_0111);
+}
+
+#[test]
+fn modify_several_fields() {
+    let mut sparse = Sparse::new(0b0000_1111_0000_0000);
+
+    sparse.modify(|w| w.low(0b1001).high(0b1_0110).mid(0b01).mid(0b10));
+    assert_eq!(u16::from(sparse), 0b0110_1111_1000_1001);
+
+    // Fields that aren't written keep their values
+    sparse.modify(|w| w.high(0));
+    assert_eq!(u16::from(sparse), 0b0000_1111_1000_1001);
+
+    sparse.modify(|w| w);
+    assert_eq!(u16::from(sparse), 0b0000_1111_1000_1001);
+
+    let mut wide = Wide::new(0);
+    wide.modify(|w| w.b0(1).b127(1));
+    assert_eq!(u128::from(wide), 1 << 127 | 1);
+
+    let mut control = registers::Control::new(0);
+    control.modify(|w| w.enable(1).mode(0b11).level(0b101));
+    assert_eq!(u8::from(control), 0b0010_1111);
+
+    // The writer can be named, so writes can be factored out
+    fn clear_ends(w: Sparse::Writer) -> Sparse::Writer {
+        w.low(0).high(0)
+    }
+
+    sparse.modify(clear_ends);
+    assert_eq!(u16::from(sparse), 0b0000_1111_1000_0000);
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn field_groups() {
+    let mut oscillator = Oscillator::new(0b11_101010_0000_0101);
+
+    assert_eq!(oscillator.trim.get(), (0b101010, 0b0101));
+    assert_eq!(oscillator.everything.get(), (0b11,));
+    assert_eq!(Oscillator::trim::POSITIONED_MASK, 0b00_111111_0000_1111);
+
+    oscillator.trim.set((0b111_000111, 0b1_1010));
+    assert_eq!(u16::from(oscillator), 0b11_000111_0000_1010);
+
+    let mut control = registers::Control::new(0b0100_0001);
+    control.config.set((0b10, 0b011));
+    assert_eq!(control.config.get(), (0b10, 0b011));
+    assert_eq!(registers::state(&control), (1, 1));
+    assert_eq!(u8::from(control), 0b0101_1101);
+
+    let mut wide = Wide::new(0b1010_0101_1111);
+    assert_eq!(wide.low_bits.get(), (1, 0, 1, 0, 0, 1, 0, 1, 1, 1, 1, 1));
+
+    wide.low_bits.set((0, 0, 0, 0, 1, 1, 1, 1, 0, 0, 0, 1));
+    assert_eq!(u128::from(wide), 0b0000_1111_0001);
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn construct_from_fields() {
+    const DEFAULT: Defaults::Defaults = from_fields!(Defaults {});
+    const CUSTOM: Defaults::Defaults = from_fields!(Defaults { plain: 0b1001, byte: 0x12, });
+
+    assert_eq!(Defaults::three::DEFAULT, 0b101);
+    assert_eq!(Defaults::plain::DEFAULT, 0);
+    assert_eq!(Defaults::Defaults::DEFAULTS, 0b1111_1111_0_101_0000);
+
+    assert_eq!(u16::from(DEFAULT), Defaults::Defaults::DEFAULTS);
+    assert_eq!(u16::from(CUSTOM), 0b0001_0010_0_101_1001);
+
+    let sparse = from_fields!(Sparse { high: 0b1010, low: 0b0110, mid: 0b11 });
+    assert_eq!(u16::from(sparse), 0b1010_0000_1100_0110);
+
+    let control = from_fields!(self::registers::Control { enable: 1, mode: 0b10 });
+    assert_eq!(u8::from(control), 0b0000_0101);
+
+    // Values don't have to be constant
+    const fn sparse_with(low: u16, high: u16) -> Sparse::Sparse {
+        from_fields!(Sparse { low: low, mid: 0b11, high: high + 1 })
+    }
+    const SPARSE: Sparse::Sparse = sparse_with(0b0110, 0b1001);
+
+    let high = std::hint::black_box(0b1001);
+    assert_eq!(sparse_with(0b0110, high), sparse);
+    assert_eq!(SPARSE, sparse);
+}
+
+#[test]
+#[should_panic(expected = "the value of field `high` doesn't fit into it")]
+fn construct_from_fields_too_wide() {
+    let high = std::hint::black_box(0b1_0000);
+    from_fields!(Sparse { high: high });
+}
+
+#[test]
+fn const_accessors() {
+    const SPARSE: Sparse::Sparse = Sparse::Sparse::ZERO
+        .with_low(0b1_0110)
+        .with_high(0b1010)
+        .with_mid(0b11);
+    const HIGH: u16 = SPARSE.get_high();
+    const CLEARED: Sparse::Sparse = {
+        let mut sparse = SPARSE;
+        sparse.set_high(0);
+        sparse
+    };
+
+    static WIDE: [Wide::Wide; 2] = [Wide::Wide::ZERO.with_b127(1), Wide::Wide::ZERO.with_b0(1)];
+
+    assert_eq!(u16::from(SPARSE), 0b1010_0000_1100_0110);
+    assert_eq!(HIGH, 0b1010);
+    assert_eq!(u16::from(CLEARED), 0b0000_0000_1100_0110);
+    assert_eq!(u128::from(WIDE[0]) | u128::from(WIDE[1]), 1 << 127 | 1);
+    assert_eq!(u16::from(FullWidth::FullWidth::ZERO.with_all(0xFFFF)), 0xFFFF);
+
+    // With the fields' visibility
+    let control = registers::Control::Control::ZERO.with_enable(1).with_mode(0b11).with_level(0b101);
+    assert_eq!((control.get_enable(), control.get_mode(), control.get_level()), (1, 0b11, 0b101));
+    assert!(!registers::is_locked(&control));
+}
+
+#[allow(clippy::clone_on_copy)]
+#[test]
+fn fields_named_like_methods() {
+    use core::cmp::Ordering;
+    type Names = MethodNames::MethodNames;
+
+    let mut names = Names::ZERO.with_x(1).with_get_x(0).with_set_x(1).with_clone(1).with_max(1);
+    assert_eq!((names.get_x(), names.get_get_x(), names.get_set_x(), names.get_clone(), names.get_max()), (1, 0, 1, 1, 1));
+
+    // The bitfield's methods aren't shadowed by the fields
+    let copy: Names = names.clone();
+    assert!(names.eq(&copy));
+    assert_eq!(names.cmp(&copy), Ordering::Equal);
+    assert_eq!(names.max(Names::ZERO), names);
+    assert_eq!(names.diff(&copy).count(), 0);
+    assert_eq!(names.get::<MethodNames::x>(), 1);
+    assert_eq!(names.with::<MethodNames::get>(1).get.get(), 1);
+
+    names.set::<MethodNames::set>(1);
+    names.modify(|w| w.modify(1).hash(1));
+    assert_eq!((names.modify.get(), names.hash.get(), names.set.get()), (1, 1, 1));
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn builder() {
+    // Fields with defaults are optional
+    let defaults = Defaults::builder().plain(0b1001).build();
+    assert_eq!(u16::from(defaults), 0b1111_1111_0_101_1001);
+
+    let overridden = Defaults::builder().byte(0x1FF).plain(1).three(0).build();
+    assert_eq!(u16::from(overridden), 0b1111_1111_0_000_0001);
+
+    // Setters follow the fields' visibility
+    let control = registers::locked(2, 0b101);
+    assert_eq!(u8::from(control), 0b1_101_10_1);
+    let _unfinished = registers::Control::builder().enable(0).mode(1).level(2);
+
+    let wide = Wide::builder()
+        .b0(1).b1(0).b2(0).b3(0).b4(0).b5(0).b6(0).b7(0).b8(0).b9(0).b10(0).b11(0).b12(0).b13(0).b14(0).b15(0)
+        .b16(0).b17(0).b18(0).b19(0).b20(0).b21(0).b22(0).b23(0).b24(0).b25(0).b26(0).b27(0).b28(0).b29(0).b30(0).b31(0)
+        .b32(0).b33(0).b34(0).b35(0).b36(0).b37(0).b38(0).b39(0).b40(0).b41(0).b42(0).b43(0).b44(0).b45(0).b46(0).b47(0)
+        .b48(0).b49(0).b50(0).b51(0).b52(0).b53(0).b54(0).b55(0).b56(0).b57(0).b58(0).b59(0).b60(0).b61(0).b62(0).b63(0)
+        .b64(0).b65(0).b66(0).b67(0).b68(0).b69(0).b70(0).b71(0).b72(0).b73(0).b74(0).b75(0).b76(0).b77(0).b78(0).b79(0)
+        .b80(0).b81(0).b82(0).b83(0).b84(0).b85(0).b86(0).b87(0).b88(0).b89(0).b90(0).b91(0).b92(0).b93(0).b94(0).b95(0)
+        .b96(0).b97(0).b98(0).b99(0).b100(0).b101(0).b102(0).b103(0).b104(0).b105(0).b106(0).b107(0).b108(0).b109(0).b110(0).b111(0)
+        .b112(0).b113(0).b114(0).b115(0).b116(0).b117(0).b118(0).b119(0).b120(0).b121(0).b122(0).b123(0).b124(0).b125(0).b126(0).b127(1)
+        .build();
+    assert_eq!(u128::from(wide), 1 << 127 | 1);
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn typed_fields() {
+    let mut typed = Typed::new(0);
+
+    typed.mode.set(FieldValue::new(0b11).unwrap());
+    typed.divider.set(FieldValue::masked(0xF_ABC));
+    assert_eq!(u16::from(typed), 0xABC_3);
+
+    assert_eq!(typed.mode.get(), FieldValue::MAX);
+    assert_eq!(typed.divider.get().value(), 0xABC);
+    assert!(FieldValue::DEFAULT < typed.mode.get());
+    assert_eq!(FieldValue::<Typed::mode>::DEFAULT.value(), 2);
+    assert_eq!(FieldValue::<Typed::mode>::new(0b100), Err(FieldError::ValueTooWide { size: 2 }));
+
+    // Raw values still go through `Field` and `Bitfield`
+    assert_eq!(typed.divider.set_checked(0x1000), Err(0));
+    assert_eq!(Field::get(&typed.divider), 0xABC);
+    assert_eq!(typed.get::<Typed::mode>(), 0b11);
+    assert_eq!(std::format!("{:?}", typed), "Typed(mode: 3, divider: 2748)");
+    assert_eq!(std::format!("{} {:?}", typed.mode.get(), typed.mode.get()), "3 FieldValue(3)");
+
+    // So do the bitfield's accessors, the writer's methods and the builder's setters
+    const ZERO_DIVIDER: Typed::Typed = Typed::Typed::ZERO.with_mode(FieldValue::MAX);
+    let divider: FieldValue<Typed::divider> = typed.get_divider();
+    assert_eq!((divider.value(), ZERO_DIVIDER.get_mode()), (0xABC, FieldValue::MAX));
+
+    typed.set_divider(FieldValue::masked(0x123));
+    typed.modify(|w| w.mode(FieldValue::new(1).unwrap()));
+    assert_eq!(u16::from(typed), 0x123_1);
+
+    let built = Typed::builder().divider(divider).build();
+    assert_eq!(u16::from(built), 0xABC_2);
+}
+
+#[test]
+fn field_arithmetic() {
+    let mut sparse = Sparse::new(0);
+
+    // Only the field's bits change, even when the result doesn't fit into it
+    assert_eq!(sparse.high.wrapping_add(0x13), 0x3);
+    assert_eq!(sparse.high.wrapping_sub(0x4), 0xF);
+    assert_eq!(sparse.low.decrement(), 0xF);
+    assert_eq!(u16::from(sparse), 0xF00F);
+
+    assert_eq!(sparse.mid.checked_add(3), Some(3));
+    assert_eq!(sparse.mid.checked_add(1), None);
+    assert_eq!(sparse.mid.get(), 3);
+    assert_eq!(sparse.mid.saturating_sub(2), 1);
+    assert_eq!(sparse.mid.checked_sub(2), None);
+    assert_eq!(sparse.mid.saturating_sub(2), 0);
+    assert_eq!(sparse.mid.saturating_add(0x100), 3);
+    assert_eq!(sparse.low.increment(), 0);
+    assert_eq!(u16::from(sparse), 0xF0C0);
+
+    // Full-width fields wrap and saturate at the base type's bounds
+    let mut full = FullWidth::new(0xFFFE);
+    assert_eq!(full.all.increment(), 0xFFFF);
+    assert_eq!(full.all.checked_add(1), None);
+    assert_eq!(full.all.wrapping_add(2), 1);
+    assert_eq!(full.all.saturating_sub(2), 0);
+    assert_eq!(full.all.saturating_add(0xFFFF), 0xFFFF);
+
+    let mut wide = Wide::new(0);
+    assert_eq!(wide.b127.decrement(), 1);
+    assert_eq!(u128::from(wide), 1 << 127);
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn field_bit_operations() {
+    let mut sparse = Sparse::new(0b0110_0000_01_00_1010);
+
+    assert_eq!((sparse.low.count_ones(), sparse.high.count_ones(), sparse.mid.count_ones()), (2, 2, 1));
+    assert_eq!((sparse.low.leading_zeros(), sparse.low.trailing_zeros()), (0, 1));
+    assert_eq!((sparse.high.leading_zeros(), sparse.high.trailing_zeros()), (1, 1));
+    assert!(sparse.high.set_bits().eq([1, 2]));
+    assert_eq!(sparse.low.set_bits().len(), 2);
+
+    assert_eq!(sparse.mid.toggle_all(), 0b10);
+    assert_eq!(sparse.high.reverse_bits(), 0b0110);
+    assert_eq!(sparse.low.reverse_bits(), 0b0101);
+    assert_eq!(u16::from(sparse), 0b0110_0000_10_00_0101);
+
+    // Empty and full-width fields
+    let mut full = FullWidth::new(0);
+    assert_eq!((full.all.leading_zeros(), full.all.trailing_zeros()), (16, 16));
+    assert_eq!(full.all.set_bits().next(), None);
+    assert_eq!(full.all.toggle_all(), 0xFFFF);
+    assert_eq!(full.all.set_bits().last(), Some(15));
+    full.all.set(1);
+    assert_eq!(full.all.reverse_bits(), 0x8000);
+
+    let wide = Wide::new(1 << 127);
+    assert_eq!((wide.b127.count_ones(), wide.b127.leading_zeros(), wide.b126.trailing_zeros()), (1, 0, 1));
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn bitwise_operators() {
+    let a = Sparse::new(0b1100_0000_11_00_1010);
+    let b = Sparse::new(0b1010_0000_01_00_0110);
+
+    assert_eq!(u16::from(a & b), 0b1000_0000_01_00_0010);
+    assert_eq!(u16::from(a | b), 0b1110_0000_11_00_1110);
+    assert_eq!(u16::from(a ^ b), 0b0110_0000_10_00_1100);
+    assert_eq!(u16::from(!a), 0b0011_1111_00_11_0101);
+
+    // Raw masks
+    assert_eq!((a & mask_of!(Sparse: high)).high.get(), 0b1100);
+    assert_eq!(u16::from(a | 1), 0b1100_0000_11_00_1011);
+    assert_eq!(u16::from(a ^ 0xFFFF), u16::from(!a));
+
+    let mut c = a;
+    c &= b;
+    assert_eq!(c, a & b);
+    c |= 0b1111;
+    c ^= a;
+    assert_eq!(u16::from(c), 0b0100_0000_10_00_0101);
+    c ^= !c;
+    assert_eq!(u16::from(c), 0xFFFF);
+}
+
+#[test]
+fn comparisons() {
+    use std::collections::{ BTreeSet, HashMap };
+
+    let low = Sparse::new(0x00_01);
+    let high = Sparse::new(0x10_00);
+
+    assert!(low < high);
+    assert_eq!(high.cmp(&low), core::cmp::Ordering::Greater);
+    assert_eq!(low.max(high), high);
+    assert_ne!(low, high);
+    assert_eq!(low, Sparse::new(1));
+
+    let mut names = HashMap::new();
+    names.insert(low, "low");
+    names.insert(high, "high");
+    assert_eq!(names[&Sparse::new(0x10_00)], "high");
+
+    let ordered: BTreeSet<_> = [high, low, high].iter().copied().collect();
+    assert!(ordered.into_iter().eq([low, high]));
+
+    // `#[no_compare]` leaves the comparisons to the user
+    assert_eq!(Status::new(0b1010_0001), Status::new(0b0000_0001));
+    assert_ne!(Status::new(0b0000_0000), Status::new(0b0000_0001));
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn masked_equality() {
+    let a = Sparse::new(0b1010_1111_01_11_0001);
+    let b = Sparse::new(0b1010_0000_01_00_0001);
+
+    // Only skipped bits (4..6) and bits without a field (8..12) differ
+    assert_ne!(a, b);
+    assert!(a.eq_fields(&b));
+    assert!(!a.eq_fields(&Sparse::new(0b1010_1111_01_11_0011)));
+    assert!(a.eq_masked(&Sparse::new(0b0000_0000_01_00_0000), mask_of!(Sparse: mid)));
+    assert!(a.eq_masked(&Sparse::new(0xFFFF), 0));
+
+    assert_eq!(Masked::<_, NamedFields>::new(a), Masked::new(b));
+    assert_ne!(Masked::<_, (Sparse::low,)>::new(a), Masked::new(Sparse::new(0)));
+    assert_eq!(Masked::<_, (Sparse::high, Sparse::mid)>::new(a), Masked::new(Sparse::new(0b1010_0000_01_00_0000)));
+    assert_eq!(Masked::<Sparse::Sparse, (Sparse::high, Sparse::mid)>::mask(), 0b1111_0000_11_00_0000);
+
+    let mut cache = std::collections::HashMap::new();
+    cache.insert(Masked::<_, NamedFields>::new(a), "a");
+    assert_eq!(cache.get(&b.into()), Some(&"a"));
+    assert_eq!(cache.keys().next().unwrap().into_inner(), a);
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn diff() {
+    use super::FieldChange;
+
+    let old = Sparse::new(0b1010_0000_01_00_0001);
+    let new = Sparse::new(0b0110_1001_01_10_0001);
+
+    let changes: std::vec::Vec<_> = old.diff(&new).collect();
+    assert_eq!(changes, [
+        FieldChange { name: "high", offset: 12, size: 4, old: 0b1010, new: 0b0110, reserved: false },
+        FieldChange { name: "_", offset: 4, size: 2, old: 0, new: 0b10, reserved: true },
+        FieldChange { name: "_", offset: 8, size: 4, old: 0, new: 0b1001, reserved: true }
+    ]);
+    assert_eq!(std::format!("{}", old.diff(&new)), "high: 10 -> 6, _ @ 4: 0 -> 2, _ @ 8: 0 -> 9");
+    assert_eq!(std::format!("{}", new.diff(&old).next().unwrap()), "high: 6 -> 10");
+    assert_eq!(std::format!("{}", old.diff(&old)), "");
+
+    // Full-width and signed base types
+    assert_eq!(std::format!("{}", FullWidth::new(0).diff(&FullWidth::new(0xFFFF))), "all: 0 -> 65535");
+    assert_eq!(std::format!("{}", Wide::new(1 << 127).diff(&Wide::new(1))), "b0: 0 -> 1, b127: 1 -> 0");
+    assert_eq!(std::format!("{}", Signed::new(0b0_0_101_001).diff(&Signed::new(-0b1000_0000))), "low: 1 -> 0, high: 5 -> 0, _ @ 6: 0 -> 2");
+}
+
+#[allow(clippy::unusual_byte_groupings)]
+#[test]
+fn pattern_matching() {
+    const HIGH: u16 = 0b1010;
+
+    let sparse = Sparse::new(0b1010_0000_01_00_0110);
+
+    assert!(bf_match!(sparse, Sparse { high: HIGH, mid: 1 }));
+    assert!(bf_match!(sparse, Sparse { low: 0b0110, }));
+    assert!(bf_match!(&sparse, Sparse {}));
+    assert!(!bf_match!(sparse, Sparse { high: HIGH, low: 0 }));
+
+    let control = registers::locked(2, 0b101);
+    let describe = |control: registers::Control::Control| bf_match!(control, {
+        registers::Control { enable: 0 } => "disabled",
+        registers::Control { mode: 2, level: 0b101 } => {
+            if registers::is_locked(&control) { "locked" } else { "unlocked" }
+        },
+        registers::Control { mode: 2 } => "mode 2",
+        _ => "other",
+    });
+
+    assert_eq!(describe(control), "locked");
+    assert_eq!(describe(control & !mask_of!(registers::Control: level)), "mode 2");
+    assert_eq!(describe(control ^ 1), "disabled");
+    assert_eq!(describe(registers::Control::Control::from(0b1_000_01_1)), "other");
+}
+
+#[test]
+fn shadowed_prelude() {
+    use shadowed_prelude::Shadowed;
+
+    let shadowed = Shadowed::new(0b0101_0001);
+    assert_eq!(shadowed.mode.get(), 1);
+    assert_eq!(shadowed.level.get(), 5);
+    assert_eq!(<Shadowed::Shadowed as Bitfield>::DEFAULTS, 0b0101_0000);
+
+    assert_eq!(Shadowed::Shadowed::from(0b0101_0001), shadowed);
+    assert!(shadowed < Shadowed::new(0b0101_0010));
+    assert_eq!(std::format!("{:?}", shadowed), "Shadowed(mode: 1, level: 5)")
+}
+
+#[test]
+fn signed_masks() {
+    assert_eq!(WithSign::low::MASK, 0b0111_1111);
+    assert_eq!(WithSign::sign::MASK, 1);
+    assert_eq!(WithSign::sign::POSITIONED_MASK, i8::MIN);
+    assert_eq!(AllSigned::all::MASK, -1);
+
+    let mut with_sign = WithSign::new(-1);
+    assert_eq!(with_sign.low.get(), 0b0111_1111);
+    assert_eq!(with_sign.sign.get(), 1);
+
+    with_sign.sign.set(0);
+    assert_eq!(i8::from(with_sign), i8::MAX);
+
+    let masks: std::vec::Vec<i8> = <WithSign::WithSign as Bitfield>::LAYOUT.iter().map(|info| info.mask).collect();
+    assert_eq!(masks, [0b0111_1111, 1]);
+    assert_eq!(<WithSign::WithSign as Bitfield>::USED_MASK, -1);
+}
@@ -0,0 +1,144 @@
+//! Values of fields that can't be mixed up between fields, see [FieldValue].
+
+use core::{
+    cmp::Ordering,
+    fmt::{ self, Debug, Display },
+    marker::PhantomData
+};
+use crate::{ Bitfield, Field, FieldError };
+
+/// A field that knows which bitfield it belongs to, so that its values can be typed by the field alone.
+/// Implemented for all fields.
+pub trait TypedField: Field<<Self as TypedField>::Bitfield> {
+    /// The bitfield that the field belongs to.
+    type Bitfield: Bitfield;
+}
+
+/// The value of the field `F`, which fits into the field.
+///
+/// Fields of bitfields marked `#[typed]` return this from `get` and only accept this in `set`, so that
+/// the value of one field can't be written into another by mistake. Raw values are converted explicitly,
+/// with [Self::new] (checked), [Self::masked] or [Field::set_checked], and read back with [Self::value].
+///
+/// Example:
+/// ```
+/// use simple_bitfield::{ bitfield, Field, FieldValue, FieldError };
+///
+/// bitfield! {
+///     #[typed]
+///     struct Control<u8> {
+///         mode: 2,
+///         divider: 6
+///     }
+/// }
+///
+/// fn main() {
+///     let mut control = Control::new(0);
+///
+///     control.mode.set(FieldValue::new(2).unwrap());
+///     control.divider.set_checked(40).unwrap();
+///     assert_eq!(control.mode.get().value(), 2);
+///     assert_eq!(control.divider.get(), FieldValue::new(40).unwrap());
+///
+///     assert_eq!(FieldValue::<Control::mode>::new(4), Err(FieldError::ValueTooWide { size: 2 }));
+///     assert_eq!(FieldValue::<Control::mode>::masked(0b111).value(), 0b11);
+/// }
+/// ```
+///
+/// Values of different fields have different types:
+/// ```compile_fail
+/// use simple_bitfield::bitfield;
+///
+/// bitfield! {
+///     #[typed]
+///     struct Control<u8> {
+///         mode: 2,
+///         divider: 6
+///     }
+/// }
+///
+/// fn main() {
+///     let mut control = Control::new(0);
+///
+///     control.mode.set(control.divider.get()); // Error: expected `FieldValue<mode>`, found `FieldValue<divider>`
+/// }
+/// ```
+pub struct FieldValue<F: TypedField> {
+    value: <F::Bitfield as Bitfield>::BaseType,
+    field: PhantomData<fn() -> F>
+}
+
+impl<F: TypedField> FieldValue<F> {
+    /// The largest value of the field, with all its bits set.
+    pub const MAX: Self = FieldValue { value: F::MASK, field: PhantomData };
+
+    /// The field's [default value](Field::DEFAULT).
+    pub const DEFAULT: Self = FieldValue { value: F::DEFAULT, field: PhantomData };
+
+    /// Converts a raw value into a value of the field.
+    /// Returns an error if the value is wider than the field.
+    pub fn new(value: <F::Bitfield as Bitfield>::BaseType) -> Result<Self, FieldError> {
+        if value & F::MASK != value {
+            Err(FieldError::ValueTooWide { size: F::SIZE })
+        } else {
+            Ok(FieldValue { value, field: PhantomData })
+        }
+    }
+
+    /// Converts a raw value into a value of the field. If the value is wider than the field,
+    /// its lowest [Field::SIZE] bits will be used.
+    pub fn masked(value: <F::Bitfield as Bitfield>::BaseType) -> Self {
+        FieldValue { value: value & F::MASK, field: PhantomData }
+    }
+
+    /// Returns the raw value.
+    pub const fn value(self) -> <F::Bitfield as Bitfield>::BaseType {
+        self.value
+    }
+
+    /// Wraps a raw value that is known to fit into the field, in `const` contexts. Used internally.
+    #[doc(hidden)]
+    pub const fn __from_masked(value: <F::Bitfield as Bitfield>::BaseType) -> Self {
+        FieldValue { value, field: PhantomData }
+    }
+}
+
+impl<F: TypedField> Clone for FieldValue<F> {
+    fn clone(&self) -> Self {
+        *self
+    }
+}
+
+impl<F: TypedField> Copy for FieldValue<F> {}
+
+impl<F: TypedField> Debug for FieldValue<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_tuple("FieldValue").field(&self.value).finish()
+    }
+}
+
+impl<F: TypedField> Display for FieldValue<F> {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        Display::fmt(&self.value, f)
+    }
+}
+
+impl<F: TypedField> PartialEq for FieldValue<F> {
+    fn eq(&self, other: &Self) -> bool {
+        self.value == other.value
+    }
+}
+
+impl<F: TypedField> Eq for FieldValue<F> where <F::Bitfield as Bitfield>::BaseType: Eq {}
+
+impl<F: TypedField> PartialOrd for FieldValue<F> where <F::Bitfield as Bitfield>::BaseType: Ord {
+    fn partial_cmp(&self, other: &Self) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl<F: TypedField> Ord for FieldValue<F> where <F::Bitfield as Bitfield>::BaseType: Ord {
+    fn cmp(&self, other: &Self) -> Ordering {
+        self.value.cmp(&other.value)
+    }
+}